@@ -0,0 +1,129 @@
+//! Plan and simulate reverse Dutch auctions offline.
+//!
+//! `auction-cli plan` prints the price schedule, duration, escrow and TTL of an auction.
+//! `auction-cli simulate` runs the real contract in a test environment, with scripted sellers.
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use auction_pricing::ScheduleParams;
+use clap::{Args, Parser, Subcommand};
+
+use crate::params::AuctionParams;
+use crate::plan::Plan;
+use crate::simulate::{simulate, Action};
+
+mod params;
+mod plan;
+mod simulate;
+
+/// Maximum TTL of entries on Stellar Mainnet, in ledgers
+const DEFAULT_MAX_TTL: u32 = 3_110_400;
+
+#[derive(Parser)]
+#[command(name = "auction-cli", version, about = "Plan and simulate reverse Dutch auctions offline")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the price schedule, duration, escrow and TTL of an auction
+    Plan {
+        #[command(flatten)]
+        auction: AuctionArgs,
+    },
+    /// Run the auction contract in a test environment with scripted sellers
+    Simulate {
+        #[command(flatten)]
+        auction: AuctionArgs,
+        /// Ledger at which a seller tries to sell. Can be repeated
+        #[arg(long = "sell-at", value_name = "LEDGER")]
+        sell_at: Vec<u32>,
+        /// Ledger at which the buyer closes the auction
+        #[arg(long = "close-at", value_name = "LEDGER")]
+        close_at: Option<u32>,
+    },
+}
+
+#[derive(Args)]
+struct AuctionArgs {
+    /// JSON file with the auction data, as returned by get_auction_data
+    #[arg(long, conflicts_with_all = ["start_bid", "incr_amount", "incr_times", "incr_interval", "start_ledger", "cancel_notice"])]
+    json: Option<PathBuf>,
+    /// Starting bid, in stroops
+    #[arg(long, required_unless_present = "json")]
+    start_bid: Option<i128>,
+    /// Amount the bid increases each interval
+    #[arg(long, required_unless_present = "json")]
+    incr_amount: Option<i128>,
+    /// Maximum number of times the bid increases
+    #[arg(long, required_unless_present = "json")]
+    incr_times: Option<u32>,
+    /// Number of ledgers between bid increases
+    #[arg(long, required_unless_present = "json")]
+    incr_interval: Option<u32>,
+    /// Ledger at which the auction starts. Defaults to the current ledger
+    #[arg(long)]
+    start_ledger: Option<u32>,
+    /// Number of ledgers between closing a running auction and the refund
+    #[arg(long, default_value_t = 0)]
+    cancel_notice: u32,
+    /// Ledger at which the auction is set up
+    #[arg(long, default_value_t = 0)]
+    current_ledger: u32,
+    /// Maximum TTL of the network, in ledgers
+    #[arg(long, default_value_t = DEFAULT_MAX_TTL)]
+    max_ttl: u32,
+}
+
+impl AuctionArgs {
+    fn params(&self) -> Result<AuctionParams, String> {
+        if let Some(json) = &self.json {
+            return AuctionParams::from_json_file(json)
+        }
+        Ok(AuctionParams {
+            schedule: ScheduleParams {
+                start_ledger: self.start_ledger.unwrap_or(self.current_ledger),
+                start_bid: self.start_bid.unwrap(),
+                incr_amount: self.incr_amount.unwrap(),
+                incr_interval: self.incr_interval.unwrap(),
+                incr_times: self.incr_times.unwrap(),
+            },
+            cancel_notice: self.cancel_notice,
+        })
+    }
+}
+
+/// Run the command and write its output to `output`. The schedule of a plan is written as it is calculated
+fn run(cli: Cli, output: &mut impl Write) -> Result<(), String> {
+    let written = match cli.command {
+        Command::Plan { auction } => {
+            let plan = Plan::new(auction.params()?, auction.current_ledger, auction.max_ttl)?;
+            write!(output, "{plan}")
+        }
+        Command::Simulate { auction, sell_at, close_at } => {
+            let mut script: Vec<(u32, Action)> = sell_at.into_iter().map(|ledger| (ledger, Action::Sell)).collect();
+            if let Some(ledger) = close_at {
+                script.push((ledger, Action::Close));
+            }
+            let simulation = simulate(&auction.params()?, auction.current_ledger, auction.max_ttl, &script)?;
+            write!(output, "{simulation}")
+        }
+    };
+    written.and_then(|_| output.flush()).map_err(|err| err.to_string())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse(), &mut BufWriter::new(io::stdout().lock())) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
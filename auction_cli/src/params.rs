@@ -0,0 +1,97 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use auction_pricing::ScheduleParams;
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+/// The parameters of an auction that the tool plans and simulates
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuctionParams {
+    pub schedule: ScheduleParams,
+    pub cancel_notice: u32,
+}
+
+/// The auction data as returned by the contract, for example by `get_auction_data` or in the `created` event.
+/// The addresses are not needed to plan an auction, so they are ignored
+#[derive(Debug, Deserialize)]
+struct DataJson {
+    auction_start_ledger: u32,
+    #[serde(deserialize_with = "amount")]
+    bid_start_amount: i128,
+    #[serde(deserialize_with = "amount")]
+    bid_incr_amount: i128,
+    bid_incr_interval: u32,
+    bid_incr_times: u32,
+    #[serde(default)]
+    cancel_notice: u32,
+}
+
+impl AuctionParams {
+    /// Read the parameters from a JSON file matching the contract's `Data`
+    pub fn from_json_file(path: &Path) -> Result<AuctionParams, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("Can not read {}: {err}", path.display()))?;
+        AuctionParams::from_json(&json)
+    }
+
+    /// Parse the parameters from JSON matching the contract's `Data`
+    pub fn from_json(json: &str) -> Result<AuctionParams, String> {
+        let data: DataJson = serde_json::from_str(json).map_err(|err| format!("Invalid auction data: {err}"))?;
+        Ok(AuctionParams {
+            schedule: ScheduleParams {
+                start_ledger: data.auction_start_ledger,
+                start_bid: data.bid_start_amount,
+                incr_amount: data.bid_incr_amount,
+                incr_interval: data.bid_incr_interval,
+                incr_times: data.bid_incr_times,
+            },
+            cancel_notice: data.cancel_notice,
+        })
+    }
+
+    /// Check the parameters the same way `setup_auction` does, apart from the addresses
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schedule.start_bid < 1 || self.schedule.incr_amount < 1 {
+            return Err("The starting bid and bid increase must be positive".into())
+        }
+        if self.schedule.incr_interval == 0 {
+            return Err("The bid increase interval must be at least 1 ledger".into())
+        }
+        if self.schedule.max_bid().is_none() || self.schedule.max_bid_ledger().is_none() {
+            return Err("The schedule overflows".into())
+        }
+        Ok(())
+    }
+}
+
+/// The contract's i128 amounts are written as strings by the Soroban CLI, and as numbers by other tools. Accept both
+fn amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+    struct AmountVisitor;
+
+    impl<'de> Visitor<'de> for AmountVisitor {
+        type Value = i128;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an amount as a number or a string")
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<i128, E> {
+            Ok(value as i128)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<i128, E> {
+            Ok(value as i128)
+        }
+
+        fn visit_i128<E: de::Error>(self, value: i128) -> Result<i128, E> {
+            Ok(value)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<i128, E> {
+            value.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(AmountVisitor)
+}
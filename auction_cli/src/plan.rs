@@ -0,0 +1,96 @@
+use std::fmt;
+
+use auction_pricing::{schedule, Schedule};
+use soroban_auction::{DAY_IN_LEDGERS, MIN_TTL, TTL_BUFFER};
+
+use crate::params::AuctionParams;
+
+/// Approximate number of seconds between ledgers
+pub const SECONDS_PER_LEDGER: u64 = 86_400 / DAY_IN_LEDGERS as u64;
+
+/// Everything an organiser needs to know before setting up an auction
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Plan {
+    pub params: AuctionParams,
+    /// Ledgers from the start of the auction until the maximum bid is reached
+    pub duration: u32,
+    /// Counter token the buyer has to escrow, which is the maximum bid
+    pub escrow: i128,
+    /// TTL the contract needs when the auction is set up at `current_ledger`
+    pub required_ttl: u32,
+    pub current_ledger: u32,
+    pub max_ttl: u32,
+}
+
+impl Plan {
+    /// Plan an auction that is set up at `current_ledger`, on a network with the given maximum TTL
+    pub fn new(params: AuctionParams, current_ledger: u32, max_ttl: u32) -> Result<Plan, String> {
+        params.validate()?;
+        if params.schedule.start_ledger < current_ledger {
+            return Err("The auction can not start before the current ledger".into())
+        }
+        let duration: u32 = params.schedule.duration().unwrap();
+        let required_ttl: u32 = (params.schedule.max_bid_ledger().unwrap() - current_ledger)
+                                    .saturating_add(TTL_BUFFER)
+                                    .max(MIN_TTL);
+        Ok(Plan {
+            params,
+            duration,
+            escrow: params.schedule.max_bid().unwrap(),
+            required_ttl,
+            current_ledger,
+            max_ttl,
+        })
+    }
+
+    /// The steps of the price schedule. They are calculated as they are iterated, so a long schedule is never held in memory
+    pub fn steps(&self) -> Schedule {
+        schedule(&self.params.schedule)
+    }
+
+    /// Whether the auction fits within the network's maximum TTL, which `setup_auction` requires
+    pub fn fits_max_ttl(&self) -> bool {
+        let duration_from_now = self.params.schedule.max_bid_ledger().unwrap() - self.current_ledger;
+        duration_from_now.saturating_add(TTL_BUFFER) <= self.max_ttl && self.params.cancel_notice.saturating_add(TTL_BUFFER) <= self.max_ttl
+    }
+}
+
+/// Format a number of ledgers as the approximate wall time, e.g. `1d 2h 3m 20s`
+pub fn wall_time(ledgers: u32) -> String {
+    let seconds = ledgers as u64 * SECONDS_PER_LEDGER;
+    let (days, hours, minutes, seconds) = (seconds / 86_400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+    let mut parts: Vec<String> = Vec::new();
+    for (value, unit) in [(days, "d"), (hours, "h"), (minutes, "m")] {
+        if value > 0 || !parts.is_empty() {
+            parts.push(format!("{value}{unit}"));
+        }
+    }
+    parts.push(format!("{seconds}s"));
+    parts.join(" ")
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Schedule")?;
+        writeln!(f, "  {:>10}  {:>10}  {:>16}", "ledger", "from start", "bid")?;
+        for step in self.steps() {
+            let from_start = step.ledger - self.params.schedule.start_ledger;
+            writeln!(f, "  {:>10}  {:>10}  {:>16}", step.ledger, from_start, step.bid)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Duration:        {} ledgers, ~{}", self.duration, wall_time(self.duration))?;
+        if self.params.schedule.start_ledger > self.current_ledger {
+            let wait = self.params.schedule.start_ledger - self.current_ledger;
+            writeln!(f, "Starts in:       {} ledgers, ~{}", wait, wall_time(wait))?;
+        }
+        if self.params.cancel_notice > 0 {
+            writeln!(f, "Cancel notice:   {} ledgers, ~{}", self.params.cancel_notice, wall_time(self.params.cancel_notice))?;
+        }
+        writeln!(f, "Escrow required: {} of the counter token", self.escrow)?;
+        writeln!(f, "TTL required:    {} ledgers, ~{} (network maximum {})", self.required_ttl, wall_time(self.required_ttl), self.max_ttl)?;
+        if !self.fits_max_ttl() {
+            writeln!(f, "The auction is longer than the network's maximum TTL, setup_auction will return DurationTooLong")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use soroban_auction::types::{Balances, State, Status};
+use soroban_auction::{AuctionContract, AuctionContractClient};
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Ledger};
+use soroban_sdk::{token, Address, Env};
+
+use crate::params::AuctionParams;
+
+/// A scripted action, executed at a ledger
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// A new seller, holding one auction token, tries to sell it
+    Sell,
+    /// The buyer closes the auction
+    Close,
+}
+
+/// The result of a scripted action
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    pub ledger: u32,
+    pub action: Action,
+    /// The bid before the action
+    pub current_bid: i128,
+    pub result: Result<Status, String>,
+}
+
+/// The results of a simulation, and the final state of the auction
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Simulation {
+    pub setup: Status,
+    pub outcomes: Vec<Outcome>,
+    pub state: State,
+    pub sale_price: Option<i128>,
+    pub balances: Balances,
+    /// Counter token returned to the buyer
+    pub refunded: i128,
+}
+
+/// Run the real contract in a test environment: set up the auction at `current_ledger` and execute the script in ledger order
+pub fn simulate(params: &AuctionParams, current_ledger: u32, max_ttl: u32, script: &[(u32, Action)]) -> Result<Simulation, String> {
+    // Entries live until their ledger plus the maximum TTL, which must still be a ledger
+    let last_ledger: u32 = script.iter().map(|(ledger, _)| *ledger).max().unwrap_or(0).max(current_ledger);
+    if last_ledger.checked_add(max_ttl).is_none() {
+        return Err(format!("A maximum TTL of {max_ttl} ledgers runs past the last ledger"))
+    }
+
+    // A simulation leaves nothing behind, so it does not write a snapshot of the environment either
+    let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+    env.mock_all_auths();
+
+    // Mirror the network's maximum TTL, and keep the token entries live for the whole simulation
+    env.ledger().with_mut(|li| {
+        li.sequence_number = current_ledger;
+        li.max_entry_ttl = max_ttl.saturating_add(1);
+        li.min_persistent_entry_ttl = max_ttl;
+        li.min_temp_entry_ttl = max_ttl;
+    });
+
+    let buyer = Address::generate(&env);
+    let auction_token = env.register_stellar_asset_contract(Address::generate(&env));
+    let counter_token = env.register_stellar_asset_contract(Address::generate(&env));
+    let escrow: i128 = params.schedule.max_bid().ok_or("The schedule overflows")?;
+    token::StellarAssetClient::new(&env, &counter_token).mint(&buyer, &escrow);
+    let counter_token_client = token::Client::new(&env, &counter_token);
+
+    let contract_id = env.register_contract(None, AuctionContract);
+    let auction_client = AuctionContractClient::new(&env, &contract_id);
+
+    let start_ledger = Some(params.schedule.start_ledger);
+    let setup = auction_client.try_setup_auction(&buyer, &auction_token, &counter_token,
+                                                 &params.schedule.start_bid, &params.schedule.incr_amount,
+                                                 &params.schedule.incr_times, &params.schedule.incr_interval,
+                                                 &None, &start_ledger, &params.cancel_notice);
+    let setup = match setup {
+        Ok(Ok(status)) => status,
+        other => return Err(format!("setup_auction failed: {other:?}")),
+    };
+    if setup != Status::Started && setup != Status::Scheduled {
+        return Err(format!("setup_auction returned {setup:?}"))
+    }
+
+    let mut script = script.to_vec();
+    script.sort_by_key(|(ledger, _)| *ledger);
+
+    let mut outcomes = Vec::new();
+    for (ledger, action) in script {
+        if ledger < env.ledger().sequence() {
+            return Err(format!("Ledger {ledger} is before the setup of the auction"))
+        }
+        env.ledger().with_mut(|li| li.sequence_number = ledger);
+        let current_bid = auction_client.get_bid_info_at(&ledger).current_bid;
+
+        let result = match action {
+            Action::Sell => {
+                let seller = Address::generate(&env);
+                token::StellarAssetClient::new(&env, &auction_token).mint(&seller, &1);
+                auction_client.try_sell_token(&seller, &0, &None)
+            }
+            Action::Close => auction_client.try_close_auction(),
+        };
+        let result = match result {
+            Ok(Ok(status)) => Ok(status),
+            Ok(Err(err)) => Err(format!("{err:?}")),
+            Err(err) => Err(format!("{err:?}")),
+        };
+        outcomes.push(Outcome { ledger, action, current_bid, result });
+    }
+
+    let summary = auction_client.get_auction_summary();
+    Ok(Simulation {
+        setup,
+        outcomes,
+        state: summary.state,
+        sale_price: summary.sale_price,
+        balances: summary.balances,
+        refunded: counter_token_client.balance(&buyer),
+    })
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "setup_auction: {:?}", self.setup)?;
+        writeln!(f, "  {:>10}  {:<6}  {:>16}  result", "ledger", "action", "bid")?;
+        for outcome in &self.outcomes {
+            let result = match &outcome.result {
+                Ok(status) => format!("{status:?}"),
+                Err(err) => format!("error {err}"),
+            };
+            writeln!(f, "  {:>10}  {:<6}  {:>16}  {}", outcome.ledger, format!("{:?}", outcome.action), outcome.current_bid, result)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Final state:     {:?}", self.state)?;
+        match self.sale_price {
+            Some(price) => writeln!(f, "Sale price:      {price}")?,
+            None => writeln!(f, "Sale price:      not sold")?,
+        }
+        writeln!(f, "Escrow left:     {} counter token, {} auction token", self.balances.counter_token, self.balances.token)?;
+        writeln!(f, "Buyer refunded:  {}", self.refunded)
+    }
+}
@@ -0,0 +1,119 @@
+use auction_pricing::ScheduleParams;
+use clap::Parser;
+use soroban_auction::types::{Balances, State, Status};
+
+use crate::params::AuctionParams;
+use crate::plan::{wall_time, Plan};
+use crate::simulate::{simulate, Action};
+use crate::{run, Cli, DEFAULT_MAX_TTL};
+
+const PARAMS: AuctionParams = AuctionParams {
+    schedule: ScheduleParams {
+        start_ledger: 100,
+        start_bid: 1000,
+        incr_amount: 100,
+        incr_interval: 10,
+        incr_times: 10,
+    },
+    cancel_notice: 0,
+};
+
+#[test]
+fn test_plan(){
+    let plan = Plan::new(PARAMS, 50, DEFAULT_MAX_TTL).unwrap();
+    assert_eq!(plan.steps().count(), 11);
+    let step = plan.steps().nth(3).unwrap();
+    assert_eq!((step.ledger, step.bid), (130, 1300));
+    assert_eq!((plan.duration, plan.escrow), (100, 2000));
+    assert_eq!(plan.required_ttl, soroban_auction::MIN_TTL);
+    assert!(plan.fits_max_ttl());
+
+    let output = plan.to_string();
+    assert!(output.contains("Duration:        100 ledgers, ~8m 20s"));
+    assert!(output.contains("Starts in:       50 ledgers, ~4m 10s"));
+    assert!(output.contains("Escrow required: 2000 of the counter token"));
+
+    // An auction longer than the maximum TTL is reported
+    let params = AuctionParams { schedule: ScheduleParams { incr_interval: 1_000_000, ..PARAMS.schedule }, ..PARAMS };
+    let plan = Plan::new(params, 0, DEFAULT_MAX_TTL).unwrap();
+    assert_eq!(plan.required_ttl, 10_000_100 + soroban_auction::TTL_BUFFER);
+    assert!(!plan.fits_max_ttl());
+
+    // Invalid parameters are rejected like setup_auction does
+    let params = AuctionParams { schedule: ScheduleParams { incr_interval: 0, ..PARAMS.schedule }, ..PARAMS };
+    assert!(Plan::new(params, 0, DEFAULT_MAX_TTL).is_err());
+    assert!(Plan::new(PARAMS, 101, DEFAULT_MAX_TTL).is_err());
+}
+
+#[test]
+fn test_wall_time(){
+    assert_eq!(wall_time(0), "0s");
+    assert_eq!(wall_time(12), "1m 0s");
+    assert_eq!(wall_time(17280), "1d 0h 0m 0s");
+    assert_eq!(wall_time(17280 + 721), "1d 1h 0m 5s");
+}
+
+#[test]
+fn test_params_from_json(){
+    // The Soroban CLI writes amounts as strings, and addresses are ignored
+    let json = r#"{
+        "buyer": "GBZXN7PIRZGNMHGA7MUUUF4GWPY5AYPV6LY4UV2GL6VJGIQRXFDNMADI",
+        "token": "CAS3J7GYLGXMF6TDJBBYYSE3HQ6BBSMLNUQ34T6TZMYMW2EVH34XOWMA",
+        "counter_token": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+        "auction_start_ledger": 100,
+        "bid_start_amount": "1000",
+        "bid_incr_amount": 100,
+        "bid_incr_interval": 10,
+        "bid_incr_times": 10,
+        "bid_max_amount": "2000",
+        "cancel_notice": 0
+    }"#;
+    assert_eq!(AuctionParams::from_json(json), Ok(PARAMS));
+    assert!(AuctionParams::from_json(r#"{"auction_start_ledger": 1}"#).is_err());
+    assert!(AuctionParams::from_json(&json.replace("\"1000\"", "\"a lot\"")).is_err());
+}
+
+#[test]
+fn test_simulate(){
+    // The first seller gets the bid at their ledger, later sellers are too late and only see its sale price
+    let script = [(135, Action::Sell), (90, Action::Sell), (150, Action::Sell), (160, Action::Close)];
+    let simulation = simulate(&PARAMS, 50, DEFAULT_MAX_TTL, &script).unwrap();
+    assert_eq!(simulation.setup, Status::Scheduled);
+    let results: Vec<_> = simulation.outcomes.iter().map(|outcome| (outcome.ledger, outcome.current_bid, outcome.result.clone())).collect();
+    assert_eq!(results, vec![
+        (90, 1000, Ok(Status::NotStarted)),
+        (135, 1300, Ok(Status::Fulfilled)),
+        (150, 1300, Ok(Status::NotRunning)),
+        (160, 1300, Ok(Status::Closed)),
+    ]);
+    assert_eq!((simulation.state, simulation.sale_price), (State::Closed, Some(1300)));
+    assert_eq!((simulation.balances, simulation.refunded), (Balances::default(), 700));
+
+    // Without sellers, the buyer gets the whole escrow back
+    let simulation = simulate(&PARAMS, 0, DEFAULT_MAX_TTL, &[(5000, Action::Close)]).unwrap();
+    assert_eq!((simulation.state, simulation.sale_price, simulation.refunded), (State::Aborted, None, 2000));
+
+    // A maximum TTL that runs past the last ledger is rejected, instead of overflowing
+    assert!(simulate(&PARAMS, 0, u32::MAX, &[(5000, Action::Close)]).is_err());
+    let simulation = simulate(&PARAMS, 0, u32::MAX - 5000, &[(5000, Action::Close)]).unwrap();
+    assert_eq!(simulation.state, State::Aborted);
+}
+
+#[test]
+fn test_cli(){
+    let cli = Cli::try_parse_from(["auction-cli", "plan", "--start-bid", "1000", "--incr-amount", "100",
+                                   "--incr-times", "10", "--incr-interval", "10"]).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    run(cli, &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("Escrow required: 2000 of the counter token"));
+
+    let cli = Cli::try_parse_from(["auction-cli", "simulate", "--start-bid", "1000", "--incr-amount", "100",
+                                   "--incr-times", "10", "--incr-interval", "10", "--sell-at", "25", "--close-at", "30"]).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    run(cli, &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("Sale price:      1200"));
+
+    // Either the parameters or a JSON file are required
+    assert!(Cli::try_parse_from(["auction-cli", "plan", "--start-bid", "1000"]).is_err());
+    assert!(Cli::try_parse_from(["auction-cli", "plan", "--json", "data.json", "--start-bid", "1000"]).is_err());
+}
@@ -0,0 +1,296 @@
+//! Typed client for the reverse auction contract, for Rust backends that talk to the network directly.
+//!
+//! [`AuctionClient`] builds an [`Invocation`] for every entrypoint, with the arguments encoded the way the
+//! contract decodes them. An invocation becomes an unsigned transaction envelope, which still has to be
+//! simulated, to add the footprint, resource fee and authorisations, and signed before it is submitted.
+//! Return values are decoded with [`FromScVal`], or [`decode_result`] for entrypoints that can fail with a
+//! [`ContractError`].
+//!
+//! A seller that does not submit its own transactions signs a [`SellOrder`] instead: the authorisation entry of
+//! [`AuctionClient::order_authorization`], which a relayer adds to its `settle_signed_order` transaction.
+use stellar_xdr::curr::{Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, HostFunction, InvokeContractArgs,
+                        InvokeHostFunctionOp, Memo, MuxedAccount, Operation, OperationBody, Preconditions, ScAddress,
+                        ScSymbol, ScVal, SequenceNumber, SorobanAddressCredentials, SorobanAuthorizationEntry,
+                        SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials, Transaction,
+                        TransactionEnvelope, TransactionExt, TransactionV1Envelope};
+
+pub use stellar_xdr::curr as xdr;
+
+pub use crate::scval::{decode_result, field, DecodeError, FromScVal, ToScVal};
+pub use crate::types::{BidInfo, ContractError, Data, EscrowTerms, PendingSale, SaleRecord, SellOrder, State, Status};
+
+mod scval;
+pub mod types;
+
+/// A call of one of the contract's entrypoints
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invocation {
+    pub contract: ScAddress,
+    pub function: ScSymbol,
+    pub args: Vec<ScVal>,
+}
+
+impl Invocation {
+    pub fn invoke_contract_args(&self) -> InvokeContractArgs {
+        InvokeContractArgs {
+            contract_address: self.contract.clone(),
+            function_name: self.function.clone(),
+            args: self.args.clone().try_into().expect("few arguments"),
+        }
+    }
+
+    /// An operation invoking the contract, without authorisations. Simulation returns the ones it needs
+    pub fn operation(&self) -> Operation {
+        Operation {
+            source_account: None,
+            body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+                host_function: HostFunction::InvokeContract(self.invoke_contract_args()),
+                auth: Default::default(),
+            }),
+        }
+    }
+
+    /// An unsigned transaction with the invocation as its only operation.
+    /// `sequence` is the sequence number of the transaction, one more than the current one of `source`.
+    /// The transaction has no Soroban data yet, simulate it before signing
+    pub fn transaction(&self, source: &MuxedAccount, sequence: i64, fee: u32) -> TransactionEnvelope {
+        TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: Transaction {
+                source_account: source.clone(),
+                fee,
+                seq_num: SequenceNumber(sequence),
+                cond: Preconditions::None,
+                memo: Memo::None,
+                operations: vec![self.operation()].try_into().expect("one operation"),
+                ext: TransactionExt::V0,
+            },
+            signatures: Default::default(),
+        })
+    }
+}
+
+/// The parameters of `setup_auction`. The escrow, start ledger and cancel notice are optional
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetupAuction {
+    pub buyer: ScAddress,
+    pub auction_token: ScAddress,
+    pub counter_token: ScAddress,
+    pub starting_bid: i128,
+    pub bid_incr_amount: i128,
+    pub bid_incr_times: u32,
+    pub bid_incr_interval: u32,
+    pub escrow: Option<EscrowTerms>,
+    pub start_ledger: Option<u32>,
+    pub cancel_notice: u32,
+}
+
+impl SetupAuction {
+    pub fn new(buyer: ScAddress, auction_token: ScAddress, counter_token: ScAddress,
+               starting_bid: i128, bid_incr_amount: i128, bid_incr_times: u32, bid_incr_interval: u32) -> SetupAuction {
+        SetupAuction {
+            buyer,
+            auction_token,
+            counter_token,
+            starting_bid,
+            bid_incr_amount,
+            bid_incr_times,
+            bid_incr_interval,
+            escrow: None,
+            start_ledger: None,
+            cancel_notice: 0,
+        }
+    }
+
+    /// Hold sales in escrow, so the arbiter can settle disputes
+    pub fn escrow(mut self, arbiter: ScAddress, dispute_window: u32) -> SetupAuction {
+        self.escrow = Some(EscrowTerms { arbiter, dispute_window });
+        self
+    }
+
+    /// Announce the auction in advance, to start at a later ledger
+    pub fn start_ledger(mut self, start_ledger: u32) -> SetupAuction {
+        self.start_ledger = Some(start_ledger);
+        self
+    }
+
+    /// Keep a running auction open for a notice period after the buyer closes it
+    pub fn cancel_notice(mut self, cancel_notice: u32) -> SetupAuction {
+        self.cancel_notice = cancel_notice;
+        self
+    }
+
+    fn args(&self) -> Vec<ScVal> {
+        vec![
+            self.buyer.to_scval(),
+            self.auction_token.to_scval(),
+            self.counter_token.to_scval(),
+            self.starting_bid.to_scval(),
+            self.bid_incr_amount.to_scval(),
+            self.bid_incr_times.to_scval(),
+            self.bid_incr_interval.to_scval(),
+            self.escrow.to_scval(),
+            self.start_ledger.to_scval(),
+            self.cancel_notice.to_scval(),
+        ]
+    }
+}
+
+/// Builds the invocations of an auction contract
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuctionClient {
+    pub contract: ScAddress,
+}
+
+impl AuctionClient {
+    pub fn new(contract: ScAddress) -> AuctionClient {
+        AuctionClient { contract }
+    }
+
+    fn invocation(&self, function: &str, args: Vec<ScVal>) -> Invocation {
+        Invocation { contract: self.contract.clone(), function: scval::symbol(function), args }
+    }
+
+    pub fn initialize(&self, admin: &ScAddress, salt: &[u8; 32]) -> Invocation {
+        self.invocation("initialize", vec![admin.to_scval(), salt.to_scval()])
+    }
+
+    pub fn rescue_tokens(&self, token: &ScAddress, to: &ScAddress, amount: i128) -> Invocation {
+        self.invocation("rescue_tokens", vec![token.to_scval(), to.to_scval(), amount.to_scval()])
+    }
+
+    pub fn upgrade(&self, new_wasm_hash: &[u8; 32]) -> Invocation {
+        self.invocation("upgrade", vec![new_wasm_hash.to_scval()])
+    }
+
+    pub fn migrate(&self) -> Invocation {
+        self.invocation("migrate", vec![])
+    }
+
+    pub fn get_storage_version(&self) -> Invocation {
+        self.invocation("get_storage_version", vec![])
+    }
+
+    pub fn setup_auction(&self, setup: &SetupAuction) -> Invocation {
+        self.invocation("setup_auction", setup.args())
+    }
+
+    pub fn get_bid_info(&self) -> Invocation {
+        self.invocation("get_bid_info", vec![])
+    }
+
+    pub fn get_auction_data(&self) -> Invocation {
+        self.invocation("get_auction_data", vec![])
+    }
+
+    pub fn get_state(&self) -> Invocation {
+        self.invocation("get_state", vec![])
+    }
+
+    pub fn get_bid_info_at(&self, ledger: u32) -> Invocation {
+        self.invocation("get_bid_info_at", vec![ledger.to_scval()])
+    }
+
+    pub fn get_auction_summary(&self) -> Invocation {
+        self.invocation("get_auction_summary", vec![])
+    }
+
+    pub fn get_history(&self, start: u32, limit: u32) -> Invocation {
+        self.invocation("get_history", vec![start.to_scval(), limit.to_scval()])
+    }
+
+    pub fn sell_token(&self, seller: &ScAddress, min_price: i128, valid_until_ledger: Option<u32>) -> Invocation {
+        self.invocation("sell_token", vec![seller.to_scval(), min_price.to_scval(), valid_until_ledger.to_scval()])
+    }
+
+    pub fn settle_signed_order(&self, order: &SellOrder) -> Invocation {
+        self.invocation("settle_signed_order", vec![order.to_scval()])
+    }
+
+    /// The unsigned authorisation of the seller for a relayer to settle `order`, which includes the transfer of the auction token.
+    /// The `nonce` must not have been used by the seller before, as the network consumes it when the order is settled.
+    /// Sign the hash of its [`signature_payload`], and set the signature in its credentials, before handing it to the relayer
+    pub fn order_authorization(&self, order: &SellOrder, auction_token: &ScAddress, nonce: i64, signature_expiration_ledger: u32) -> SorobanAuthorizationEntry {
+        let transfer = Invocation {
+            contract: auction_token.clone(),
+            function: scval::symbol("transfer"),
+            args: vec![order.seller.to_scval(), self.contract.to_scval(), 1_i128.to_scval()],
+        };
+        // The seller authorises the order, which is the only argument of the call
+        let settle = self.settle_signed_order(order);
+        SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+                address: order.seller.clone(),
+                nonce,
+                signature_expiration_ledger,
+                signature: ScVal::Void,
+            }),
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::ContractFn(settle.invoke_contract_args()),
+                sub_invocations: vec![SorobanAuthorizedInvocation {
+                    function: SorobanAuthorizedFunction::ContractFn(transfer.invoke_contract_args()),
+                    sub_invocations: Default::default(),
+                }].try_into().expect("one sub invocation"),
+            },
+        }
+    }
+
+    /// Pause the auction, as its buyer or operator
+    pub fn pause_auction(&self, caller: &ScAddress) -> Invocation {
+        self.invocation("pause_auction", vec![caller.to_scval()])
+    }
+
+    /// Resume the auction, as its buyer or operator
+    pub fn resume_auction(&self, caller: &ScAddress) -> Invocation {
+        self.invocation("resume_auction", vec![caller.to_scval()])
+    }
+
+    /// Amend the auction, as its buyer or operator. Only the buyer can raise the maximum bid, topping up the escrow
+    pub fn amend_auction(&self, caller: &ScAddress, current_bid: i128, bid_incr_amount: i128, extra_incr_times: u32) -> Invocation {
+        self.invocation("amend_auction", vec![caller.to_scval(), current_bid.to_scval(), bid_incr_amount.to_scval(), extra_incr_times.to_scval()])
+    }
+
+    pub fn set_operator(&self, operator: Option<&ScAddress>) -> Invocation {
+        self.invocation("set_operator", vec![operator.cloned().to_scval()])
+    }
+
+    pub fn raise_dispute(&self) -> Invocation {
+        self.invocation("raise_dispute", vec![])
+    }
+
+    pub fn resolve_dispute(&self, release: bool) -> Invocation {
+        self.invocation("resolve_dispute", vec![release.to_scval()])
+    }
+
+    pub fn finalize(&self) -> Invocation {
+        self.invocation("finalize", vec![])
+    }
+
+    pub fn close_auction(&self) -> Invocation {
+        self.invocation("close_auction", vec![])
+    }
+
+    pub fn bump(&self) -> Invocation {
+        self.invocation("bump", vec![])
+    }
+
+    pub fn reset_auction(&self) -> Invocation {
+        self.invocation("reset_auction", vec![])
+    }
+}
+
+/// The payload an address signs for an authorisation entry on the network with `network_id`, the hash of its passphrase.
+/// The signature is over the SHA-256 hash of its XDR. Entries authorised by the source account are not signed separately
+pub fn signature_payload(network_id: &[u8; 32], entry: &SorobanAuthorizationEntry) -> Option<HashIdPreimage> {
+    let SorobanCredentials::Address(credentials) = &entry.credentials else {
+        return None
+    };
+    Some(HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(*network_id),
+        nonce: credentials.nonce,
+        signature_expiration_ledger: credentials.signature_expiration_ledger,
+        invocation: entry.root_invocation.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,186 @@
+use std::fmt;
+
+use stellar_xdr::curr::{BytesM, ScAddress, ScBytes, ScError, ScMap, ScMapEntry, ScSymbol, ScVal, ScVec};
+
+use crate::types::ContractError;
+
+/// A value that can not be decoded into the expected type
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodeError {
+    pub expected: &'static str,
+    pub found: ScVal,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected {}, found {:?}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encode a value the way the contract's types are encoded
+pub trait ToScVal {
+    fn to_scval(&self) -> ScVal;
+}
+
+/// Decode a value returned by the contract
+pub trait FromScVal: Sized {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError>;
+}
+
+/// Decode the result of an entrypoint that returns `Result<T, Error>`.
+/// A contract error is returned as `Ok(Err(_))`, a value of the wrong type as `Err(_)`
+pub fn decode_result<T: FromScVal>(val: &ScVal) -> Result<Result<T, ContractError>, DecodeError> {
+    match val {
+        ScVal::Error(ScError::Contract(code)) => ContractError::from_code(*code).map(Err)
+                                                     .ok_or_else(|| mismatch("a contract error", val)),
+        _ => T::from_scval(val).map(Ok),
+    }
+}
+
+pub(crate) fn mismatch(expected: &'static str, found: &ScVal) -> DecodeError {
+    DecodeError { expected, found: found.clone() }
+}
+
+/// A symbol, which is always valid for the names the contract uses
+pub(crate) fn symbol(name: &str) -> ScSymbol {
+    name.try_into().expect("valid symbol")
+}
+
+/// Encode a struct as a map keyed by field name, sorted like the contract sorts them
+pub(crate) fn struct_to_scval(fields: Vec<(&str, ScVal)>) -> ScVal {
+    let mut fields = fields;
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+    let entries: Vec<ScMapEntry> = fields.into_iter()
+                                         .map(|(key, val)| ScMapEntry { key: ScVal::Symbol(symbol(key)), val })
+                                         .collect();
+    ScVal::Map(Some(ScMap(entries.try_into().expect("few fields"))))
+}
+
+/// Look up the field of a struct, encoded as a map keyed by field name
+pub fn field<T: FromScVal>(val: &ScVal, name: &'static str) -> Result<T, DecodeError> {
+    let ScVal::Map(Some(map)) = val else {
+        return Err(mismatch("a struct", val))
+    };
+    let entry = map.iter()
+                   .find(|entry| matches!(&entry.key, ScVal::Symbol(key) if key.as_slice() == name.as_bytes()))
+                   .ok_or_else(|| mismatch(name, val))?;
+    T::from_scval(&entry.val)
+}
+
+/// Encode a unit enum variant as a vector holding its name
+pub(crate) fn variant_to_scval(name: &str) -> ScVal {
+    ScVal::Vec(Some(ScVec(vec![ScVal::Symbol(symbol(name))].try_into().expect("one element"))))
+}
+
+/// The name of a unit enum variant, encoded as a vector holding its name
+pub(crate) fn variant_name(val: &ScVal) -> Option<&str> {
+    match val {
+        ScVal::Vec(Some(vec)) if vec.len() == 1 => match &vec[0] {
+            ScVal::Symbol(name) => std::str::from_utf8(name.as_slice()).ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl ToScVal for u32 {
+    fn to_scval(&self) -> ScVal {
+        ScVal::U32(*self)
+    }
+}
+
+impl FromScVal for u32 {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::U32(value) => Ok(*value),
+            _ => Err(mismatch("a u32", val)),
+        }
+    }
+}
+
+impl ToScVal for u64 {
+    fn to_scval(&self) -> ScVal {
+        ScVal::U64(*self)
+    }
+}
+
+impl FromScVal for u64 {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::U64(value) => Ok(*value),
+            _ => Err(mismatch("a u64", val)),
+        }
+    }
+}
+
+impl ToScVal for i128 {
+    fn to_scval(&self) -> ScVal {
+        (*self).into()
+    }
+}
+
+impl FromScVal for i128 {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::I128(parts) => Ok(parts.into()),
+            _ => Err(mismatch("an i128", val)),
+        }
+    }
+}
+
+impl ToScVal for bool {
+    fn to_scval(&self) -> ScVal {
+        ScVal::Bool(*self)
+    }
+}
+
+impl FromScVal for bool {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::Bool(value) => Ok(*value),
+            _ => Err(mismatch("a bool", val)),
+        }
+    }
+}
+
+impl ToScVal for ScAddress {
+    fn to_scval(&self) -> ScVal {
+        ScVal::Address(self.clone())
+    }
+}
+
+impl FromScVal for ScAddress {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::Address(address) => Ok(address.clone()),
+            _ => Err(mismatch("an address", val)),
+        }
+    }
+}
+
+impl ToScVal for [u8; 32] {
+    fn to_scval(&self) -> ScVal {
+        let bytes: BytesM = self.to_vec().try_into().expect("32 bytes");
+        ScVal::Bytes(ScBytes(bytes))
+    }
+}
+
+impl<T: ToScVal> ToScVal for Option<T> {
+    fn to_scval(&self) -> ScVal {
+        match self {
+            Some(value) => value.to_scval(),
+            None => ScVal::Void,
+        }
+    }
+}
+
+impl<T: FromScVal> FromScVal for Option<T> {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::Void => Ok(None),
+            _ => T::from_scval(val).map(Some),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{env, fs};
+
+use soroban_auction::types as contract;
+use soroban_auction::AuctionContract;
+use soroban_auction::testutils::{MockAccount, MockAccountClient};
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{token, Address, Bytes, Env, Symbol, TryFromVal, Val};
+use stellar_xdr::curr::{Limits, MuxedAccount, ReadXdr, ScAddress, ScError, ScSpecEntry, ScSpecUdtUnionCaseV0, ScVal, TransactionEnvelope, WriteXdr};
+
+use crate::{decode_result, signature_payload, AuctionClient, BidInfo, ContractError, Data, FromScVal, Invocation, SellOrder, SetupAuction,
+            State, Status, ToScVal};
+
+const CONTRACT: &str = "CAS3J7GYLGXMF6TDJBBYYSE3HQ6BBSMLNUQ34T6TZMYMW2EVH34XOWMA";
+const TOKEN: &str = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC";
+const COUNTER_TOKEN: &str = "CDX6RGOHIVMPECYIXPAZX4BCRQGCLPO3PBY5QC6TJLELGPADBM3JQ424";
+const ACCOUNT: &str = "GBZXN7PIRZGNMHGA7MUUUF4GWPY5AYPV6LY4UV2GL6VJGIQRXFDNMADI";
+
+fn address(strkey: &str) -> ScAddress {
+    strkey.parse().unwrap()
+}
+
+/// An invocation of every entrypoint, with fixed arguments
+fn invocations() -> Vec<Invocation> {
+    let client = AuctionClient::new(address(CONTRACT));
+    let account = address(ACCOUNT);
+    let setup = SetupAuction::new(account.clone(), address(TOKEN), address(COUNTER_TOKEN), 1000, 100, 10, 10)
+                    .escrow(account.clone(), 50)
+                    .start_ledger(100)
+                    .cancel_notice(20);
+    vec![
+        client.initialize(&account, &[3; 32]),
+        client.rescue_tokens(&address(TOKEN), &account, 5),
+        client.upgrade(&[7; 32]),
+        client.migrate(),
+        client.get_storage_version(),
+        client.setup_auction(&setup),
+        client.get_bid_info(),
+        client.get_auction_data(),
+        client.get_state(),
+        client.get_bid_info_at(150),
+        client.get_auction_summary(),
+        client.get_history(0, 10),
+        client.sell_token(&account, 1100, Some(200)),
+        client.settle_signed_order(&SellOrder { auction_id: 1, seller: account.clone(), min_price: 1100, valid_until_ledger: 200 }),
+        client.pause_auction(&account),
+        client.resume_auction(&account),
+        client.amend_auction(&account, 1200, 50, 2),
+        client.set_operator(Some(&account)),
+        client.raise_dispute(),
+        client.resolve_dispute(true),
+        client.finalize(),
+        client.close_auction(),
+        client.bump(),
+        client.reset_auction(),
+    ]
+}
+
+/// The contract's specification of a function or type
+fn spec(xdr: &[u8]) -> ScSpecEntry {
+    ScSpecEntry::from_xdr(xdr, Limits::none()).unwrap()
+}
+
+#[test]
+fn test_envelope_fixtures(){
+    // Set UPDATE_FIXTURES=1 to regenerate the fixtures after an intended change
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/envelopes.txt");
+    let source: MuxedAccount = ACCOUNT.parse().unwrap();
+    let envelopes: Vec<(String, String)> = invocations().iter().enumerate().map(|(index, invocation)| {
+        let envelope = invocation.transaction(&source, 1000 + index as i64, 100);
+        (invocation.function.to_utf8_string().unwrap(), envelope.to_xdr_base64(Limits::none()).unwrap())
+    }).collect();
+    let expected: String = envelopes.iter().map(|(function, xdr)| format!("{function} {xdr}\n")).collect();
+    if env::var("UPDATE_FIXTURES").is_ok() {
+        fs::write(path, &expected).unwrap();
+    }
+    assert_eq!(fs::read_to_string(path).unwrap(), expected);
+
+    // The fixtures decode to the transaction that was built
+    for (index, (_, xdr)) in envelopes.iter().enumerate() {
+        let TransactionEnvelope::Tx(envelope) = TransactionEnvelope::from_xdr_base64(xdr, Limits::none()).unwrap() else {
+            panic!("Not a v1 transaction")
+        };
+        assert_eq!(envelope.tx.seq_num.0, 1000 + index as i64);
+        assert!(envelope.signatures.is_empty());
+    }
+}
+
+#[test]
+fn test_invocations_match_contract_spec(){
+    let specs = [
+        AuctionContract::spec_xdr_initialize().to_vec(),
+        AuctionContract::spec_xdr_rescue_tokens().to_vec(),
+        AuctionContract::spec_xdr_upgrade().to_vec(),
+        AuctionContract::spec_xdr_migrate().to_vec(),
+        AuctionContract::spec_xdr_get_storage_version().to_vec(),
+        AuctionContract::spec_xdr_setup_auction().to_vec(),
+        AuctionContract::spec_xdr_get_bid_info().to_vec(),
+        AuctionContract::spec_xdr_get_auction_data().to_vec(),
+        AuctionContract::spec_xdr_get_state().to_vec(),
+        AuctionContract::spec_xdr_get_bid_info_at().to_vec(),
+        AuctionContract::spec_xdr_get_auction_summary().to_vec(),
+        AuctionContract::spec_xdr_get_history().to_vec(),
+        AuctionContract::spec_xdr_sell_token().to_vec(),
+        AuctionContract::spec_xdr_settle_signed_order().to_vec(),
+        AuctionContract::spec_xdr_pause_auction().to_vec(),
+        AuctionContract::spec_xdr_resume_auction().to_vec(),
+        AuctionContract::spec_xdr_amend_auction().to_vec(),
+        AuctionContract::spec_xdr_set_operator().to_vec(),
+        AuctionContract::spec_xdr_raise_dispute().to_vec(),
+        AuctionContract::spec_xdr_resolve_dispute().to_vec(),
+        AuctionContract::spec_xdr_finalize().to_vec(),
+        AuctionContract::spec_xdr_close_auction().to_vec(),
+        AuctionContract::spec_xdr_bump().to_vec(),
+        AuctionContract::spec_xdr_reset_auction().to_vec(),
+    ];
+    let invocations = invocations();
+    assert_eq!(invocations.len(), specs.len());
+    for (invocation, spec_xdr) in invocations.iter().zip(specs) {
+        let ScSpecEntry::FunctionV0(function) = spec(&spec_xdr) else {
+            panic!("Not a function")
+        };
+        assert_eq!(function.name.0, invocation.function.0);
+        assert_eq!(function.inputs.len(), invocation.args.len(), "{:?}", function.name);
+    }
+
+    // The mirrored enums have the same variants, in the same order
+    let ScSpecEntry::UdtUnionV0(status) = spec(&contract::Status::spec_xdr()) else { panic!() };
+    let names: Vec<String> = status.cases.iter().map(|case| match case {
+        ScSpecUdtUnionCaseV0::VoidV0(case) => case.name.to_utf8_string().unwrap(),
+        ScSpecUdtUnionCaseV0::TupleV0(case) => case.name.to_utf8_string().unwrap(),
+    }).collect();
+    assert_eq!(names, Status::ALL.iter().map(|status| status.name()).collect::<Vec<_>>());
+    let ScSpecEntry::UdtUnionV0(state) = spec(&contract::State::spec_xdr()) else { panic!() };
+    assert_eq!(state.cases.len(), State::ALL.len());
+    let ScSpecEntry::UdtErrorEnumV0(error) = spec(&contract::Error::spec_xdr()) else { panic!() };
+    let codes: Vec<u32> = error.cases.iter().map(|case| case.value).collect();
+    assert_eq!(codes, ContractError::ALL.iter().map(|error| *error as u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_encoding_matches_contract(){
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.sequence_number = 50);
+
+    let buyer = Address::generate(&env);
+    let auction_token = env.register_stellar_asset_contract(Address::generate(&env));
+    let counter_token = env.register_stellar_asset_contract(Address::generate(&env));
+    token::StellarAssetClient::new(&env, &counter_token).mint(&buyer, &2000);
+    let contract_id = env.register_contract(None, AuctionContract);
+
+    let sc_address = |address: &Address| ScAddress::try_from(address).unwrap();
+    let client = AuctionClient::new(sc_address(&contract_id));
+
+    // Invoke the contract with the arguments the client encodes, and decode what it returns
+    let invoke = |invocation: Invocation| -> ScVal {
+        let args: std::vec::Vec<Val> = invocation.args.iter().map(|arg| Val::try_from_val(&env, arg).unwrap()).collect();
+        let function = Symbol::new(&env, &invocation.function.to_utf8_string().unwrap());
+        let result: Val = env.invoke_contract(&contract_id, &function, soroban_sdk::Vec::from_slice(&env, &args));
+        ScVal::try_from_val(&env, &result).unwrap()
+    };
+
+    let setup = SetupAuction::new(sc_address(&buyer), sc_address(&auction_token), sc_address(&counter_token), 1000, 100, 10, 10)
+                    .start_ledger(100)
+                    .cancel_notice(20);
+    assert_eq!(decode_result::<Status>(&invoke(client.setup_auction(&setup))), Ok(Ok(Status::Scheduled)));
+
+    let data = Data::from_scval(&invoke(client.get_auction_data())).unwrap();
+    assert_eq!(data, Data {
+        buyer: sc_address(&buyer),
+        token: sc_address(&auction_token),
+        counter_token: sc_address(&counter_token),
+        auction_start_ledger: 100,
+        bid_start_amount: 1000,
+        bid_incr_amount: 100,
+        bid_incr_interval: 10,
+        bid_incr_times: 10,
+        bid_max_amount: 2000,
+        cancel_notice: 20,
+    });
+    // Encoding the mirrored type gives the same value as encoding the contract's type
+    let contract_data = soroban_auction::AuctionContractClient::new(&env, &contract_id).get_auction_data();
+    assert_eq!(data.to_scval(), ScVal::try_from(&contract_data).unwrap());
+
+    let bid_info = BidInfo::from_scval(&invoke(client.get_bid_info_at(125))).unwrap();
+    assert_eq!(bid_info, BidInfo {
+        current_bid: 1200,
+        current_ledger: 125,
+        ledgers_to_next_increase: 5,
+        max_bid: 2000,
+        max_bid_ledger: 200,
+        next_bid: 1300,
+        next_bid_ledger: 130,
+        start_ledger: 100,
+        state: State::Running,
+    });
+    assert_eq!(u32::from_scval(&invoke(client.get_storage_version())), Ok(2));
+}
+
+#[test]
+fn test_decode_errors(){
+    // Contract errors are returned as such, unknown codes and other values are not decoded
+    let error = ContractError::NotRunning.to_scval();
+    assert_eq!(error, ScVal::Error(ScError::Contract(3)));
+    assert_eq!(decode_result::<Status>(&error), Ok(Err(ContractError::NotRunning)));
+    assert!(decode_result::<Status>(&ScVal::Error(ScError::Contract(99))).is_err());
+    assert!(decode_result::<BidInfo>(&Status::Closed.to_scval()).is_err());
+    assert!(Status::from_scval(&State::Running.to_scval()).is_err());
+
+    for status in Status::ALL {
+        assert_eq!(Status::from_scval(&status.to_scval()), Ok(*status));
+    }
+    for error in ContractError::ALL {
+        assert_eq!(ContractError::from_scval(&error.to_scval()), Ok(*error));
+    }
+}
+
+#[test]
+fn test_signed_order_settles(){
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.sequence_number = 50);
+
+    let buyer = Address::generate(&env);
+    let seller = env.register_contract(None, MockAccount);
+    let auction_token = env.register_stellar_asset_contract(Address::generate(&env));
+    let counter_token = env.register_stellar_asset_contract(Address::generate(&env));
+    token::StellarAssetClient::new(&env, &auction_token).mint(&seller, &1);
+    token::StellarAssetClient::new(&env, &counter_token).mint(&buyer, &2000);
+    let contract_id = env.register_contract(None, AuctionContract);
+
+    let sc_address = |address: &Address| ScAddress::try_from(address).unwrap();
+    let client = AuctionClient::new(sc_address(&contract_id));
+    let auction_client = soroban_auction::AuctionContractClient::new(&env, &contract_id);
+    let setup = SetupAuction::new(sc_address(&buyer), sc_address(&auction_token), sc_address(&counter_token), 1000, 100, 10, 10);
+    auction_client.setup_auction(&buyer, &auction_token, &counter_token, &setup.starting_bid, &setup.bid_incr_amount,
+                                 &setup.bid_incr_times, &setup.bid_incr_interval, &None, &None, &0);
+
+    // The relayer submits the order with only the authorisation of the seller, which checks the payload the client computed
+    let order = SellOrder { auction_id: 1, seller: sc_address(&seller), min_price: 1000, valid_until_ledger: 60 };
+    let entry = client.order_authorization(&order, &sc_address(&auction_token), 7, 100);
+    env.set_auths(std::slice::from_ref(&entry));
+    let invocation = client.settle_signed_order(&order);
+    let args: std::vec::Vec<Val> = invocation.args.iter().map(|arg| Val::try_from_val(&env, arg).unwrap()).collect();
+    let function = Symbol::new(&env, &invocation.function.to_utf8_string().unwrap());
+    let result: Val = env.invoke_contract(&contract_id, &function, soroban_sdk::Vec::from_slice(&env, &args));
+    assert_eq!(decode_result::<Status>(&ScVal::try_from_val(&env, &result).unwrap()), Ok(Ok(Status::Fulfilled)));
+    assert_eq!(token::Client::new(&env, &counter_token).balance(&seller), 1000);
+
+    let network_id: [u8; 32] = env.ledger().network_id().to_array();
+    let preimage = signature_payload(&network_id, &entry).unwrap().to_xdr(Limits::none()).unwrap();
+    let payload = env.crypto().sha256(&Bytes::from_slice(&env, &preimage)).to_bytes();
+    assert_eq!(MockAccountClient::new(&env, &seller).last_payload(), Some(payload));
+}
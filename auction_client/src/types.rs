@@ -0,0 +1,369 @@
+//! The contract's types, mirrored without the Soroban SDK so that they can be used off-chain
+use stellar_xdr::curr::{ScAddress, ScError, ScVal};
+
+use crate::scval::{field, mismatch, struct_to_scval, variant_name, variant_to_scval, DecodeError, FromScVal, ToScVal};
+
+/// A unit enum of the contract, encoded as a vector holding the name of the variant
+macro_rules! unit_enum {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident),* $(,)? }) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            /// All variants, in the order the contract declares them
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+        }
+
+        impl ToScVal for $name {
+            fn to_scval(&self) -> ScVal {
+                variant_to_scval(self.name())
+            }
+        }
+
+        impl FromScVal for $name {
+            fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+                let name = variant_name(val).ok_or_else(|| mismatch(stringify!($name), val))?;
+                $name::ALL.iter()
+                          .find(|variant| variant.name() == name)
+                          .copied()
+                          .ok_or_else(|| mismatch(stringify!($name), val))
+            }
+        }
+    };
+}
+
+unit_enum!(
+    /// The status the contract's entrypoints return
+    Status {
+        AlreadyInitialised,
+        AlreadyClosed,
+        Started,
+        Aborted,
+        Closed,
+        Fulfilled,
+        NotInitialised,
+        NotRunning,
+        BidMustBePositive,
+        Reset,
+        PendingRelease,
+        NotPendingRelease,
+        DisputeRaised,
+        DisputeWindowOpen,
+        DisputeWindowClosed,
+        NotDisputed,
+        Released,
+        Refunded,
+        Paused,
+        NotPaused,
+        Resumed,
+        Amended,
+        AmendmentLowersPrice,
+        Scheduled,
+        NotStarted,
+        StartLedgerInPast,
+        Cancelling,
+        NoticePeriod,
+        AdminSet,
+        Rescued,
+        ExceedsUntracked,
+        ZeroInterval,
+        Overflow,
+        DurationTooLong,
+        IdenticalTokens,
+        BuyerIsContract,
+        Bumped,
+        Upgraded,
+        Migrated,
+        UpToDate,
+        OperatorSet,
+        NotManager,
+        EscrowHeld,
+        ExceedsEscrow,
+    }
+);
+
+unit_enum!(
+    /// The state of an auction
+    State {
+        NotStarted,
+        Running,
+        Paused,
+        Cancelling,
+        PendingRelease,
+        Disputed,
+        Fulfilled,
+        Closed,
+        Aborted,
+    }
+);
+
+/// The errors the contract fails with, encoded as a contract error code
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u32)]
+pub enum ContractError {
+    NotInitialised = 1,
+    AlreadyIntitialised = 2,
+    NotRunning = 3,
+    NotYetClosed = 4,
+    TransferAmountMismatch = 5,
+    TransferFailed = 6,
+    PriceBelowMinimum = 8,
+    SaleExpired = 9,
+    WrongAuction = 10,
+    NotDeployer = 11,
+    NotMigrated = 12,
+}
+
+impl ContractError {
+    pub const ALL: &'static [ContractError] = &[
+        ContractError::NotInitialised,
+        ContractError::AlreadyIntitialised,
+        ContractError::NotRunning,
+        ContractError::NotYetClosed,
+        ContractError::TransferAmountMismatch,
+        ContractError::TransferFailed,
+        ContractError::PriceBelowMinimum,
+        ContractError::SaleExpired,
+        ContractError::WrongAuction,
+        ContractError::NotDeployer,
+        ContractError::NotMigrated,
+    ];
+
+    pub fn from_code(code: u32) -> Option<ContractError> {
+        ContractError::ALL.iter().find(|error| **error as u32 == code).copied()
+    }
+}
+
+impl ToScVal for ContractError {
+    fn to_scval(&self) -> ScVal {
+        ScVal::Error(ScError::Contract(*self as u32))
+    }
+}
+
+impl FromScVal for ContractError {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        match val {
+            ScVal::Error(ScError::Contract(code)) => ContractError::from_code(*code).ok_or_else(|| mismatch("a contract error", val)),
+            _ => Err(mismatch("a contract error", val)),
+        }
+    }
+}
+
+/// The optional escrow of `setup_auction`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowTerms {
+    pub arbiter: ScAddress,
+    pub dispute_window: u32,
+}
+
+impl ToScVal for EscrowTerms {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("arbiter", self.arbiter.to_scval()),
+            ("dispute_window", self.dispute_window.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for EscrowTerms {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(EscrowTerms {
+            arbiter: field(val, "arbiter")?,
+            dispute_window: field(val, "dispute_window")?,
+        })
+    }
+}
+
+/// The auction data, as returned by `get_auction_data`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Data {
+    pub buyer: ScAddress,
+    pub token: ScAddress,
+    pub counter_token: ScAddress,
+    pub auction_start_ledger: u32,
+    pub bid_start_amount: i128,
+    pub bid_incr_amount: i128,
+    pub bid_incr_interval: u32,
+    pub bid_incr_times: u32,
+    pub bid_max_amount: i128,
+    pub cancel_notice: u32,
+}
+
+impl ToScVal for Data {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("buyer", self.buyer.to_scval()),
+            ("token", self.token.to_scval()),
+            ("counter_token", self.counter_token.to_scval()),
+            ("auction_start_ledger", self.auction_start_ledger.to_scval()),
+            ("bid_start_amount", self.bid_start_amount.to_scval()),
+            ("bid_incr_amount", self.bid_incr_amount.to_scval()),
+            ("bid_incr_interval", self.bid_incr_interval.to_scval()),
+            ("bid_incr_times", self.bid_incr_times.to_scval()),
+            ("bid_max_amount", self.bid_max_amount.to_scval()),
+            ("cancel_notice", self.cancel_notice.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for Data {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(Data {
+            buyer: field(val, "buyer")?,
+            token: field(val, "token")?,
+            counter_token: field(val, "counter_token")?,
+            auction_start_ledger: field(val, "auction_start_ledger")?,
+            bid_start_amount: field(val, "bid_start_amount")?,
+            bid_incr_amount: field(val, "bid_incr_amount")?,
+            bid_incr_interval: field(val, "bid_incr_interval")?,
+            bid_incr_times: field(val, "bid_incr_times")?,
+            bid_max_amount: field(val, "bid_max_amount")?,
+            cancel_notice: field(val, "cancel_notice")?,
+        })
+    }
+}
+
+/// The bid of an auction, as returned by `get_bid_info`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BidInfo {
+    pub current_bid: i128,
+    pub current_ledger: u32,
+    pub ledgers_to_next_increase: u32,
+    pub max_bid: i128,
+    pub max_bid_ledger: u32,
+    pub next_bid: i128,
+    pub next_bid_ledger: u32,
+    pub start_ledger: u32,
+    pub state: State,
+}
+
+impl ToScVal for BidInfo {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("current_bid", self.current_bid.to_scval()),
+            ("current_ledger", self.current_ledger.to_scval()),
+            ("ledgers_to_next_increase", self.ledgers_to_next_increase.to_scval()),
+            ("max_bid", self.max_bid.to_scval()),
+            ("max_bid_ledger", self.max_bid_ledger.to_scval()),
+            ("next_bid", self.next_bid.to_scval()),
+            ("next_bid_ledger", self.next_bid_ledger.to_scval()),
+            ("start_ledger", self.start_ledger.to_scval()),
+            ("state", self.state.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for BidInfo {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(BidInfo {
+            current_bid: field(val, "current_bid")?,
+            current_ledger: field(val, "current_ledger")?,
+            ledgers_to_next_increase: field(val, "ledgers_to_next_increase")?,
+            max_bid: field(val, "max_bid")?,
+            max_bid_ledger: field(val, "max_bid_ledger")?,
+            next_bid: field(val, "next_bid")?,
+            next_bid_ledger: field(val, "next_bid_ledger")?,
+            start_ledger: field(val, "start_ledger")?,
+            state: field(val, "state")?,
+        })
+    }
+}
+
+/// A pending sale, held in escrow until the dispute window closes
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingSale {
+    pub seller: ScAddress,
+    pub price: i128,
+    pub release_ledger: u32,
+}
+
+impl ToScVal for PendingSale {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("seller", self.seller.to_scval()),
+            ("price", self.price.to_scval()),
+            ("release_ledger", self.release_ledger.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for PendingSale {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(PendingSale {
+            seller: field(val, "seller")?,
+            price: field(val, "price")?,
+            release_ledger: field(val, "release_ledger")?,
+        })
+    }
+}
+
+/// A completed sale
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SaleRecord {
+    pub seller: ScAddress,
+    pub price: i128,
+    pub ledger: u32,
+    pub timestamp: u64,
+}
+
+impl ToScVal for SaleRecord {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("seller", self.seller.to_scval()),
+            ("price", self.price.to_scval()),
+            ("ledger", self.ledger.to_scval()),
+            ("timestamp", self.timestamp.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for SaleRecord {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(SaleRecord {
+            seller: field(val, "seller")?,
+            price: field(val, "price")?,
+            ledger: field(val, "ledger")?,
+            timestamp: field(val, "timestamp")?,
+        })
+    }
+}
+
+/// A sell order that the seller signs off-chain, for a relayer to settle with `settle_signed_order`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SellOrder {
+    pub auction_id: u32,
+    pub seller: ScAddress,
+    pub min_price: i128,
+    pub valid_until_ledger: u32,
+}
+
+impl ToScVal for SellOrder {
+    fn to_scval(&self) -> ScVal {
+        struct_to_scval(vec![
+            ("auction_id", self.auction_id.to_scval()),
+            ("seller", self.seller.to_scval()),
+            ("min_price", self.min_price.to_scval()),
+            ("valid_until_ledger", self.valid_until_ledger.to_scval()),
+        ])
+    }
+}
+
+impl FromScVal for SellOrder {
+    fn from_scval(val: &ScVal) -> Result<Self, DecodeError> {
+        Ok(SellOrder {
+            auction_id: field(val, "auction_id")?,
+            seller: field(val, "seller")?,
+            min_price: field(val, "min_price")?,
+            valid_until_ledger: field(val, "valid_until_ledger")?,
+        })
+    }
+}
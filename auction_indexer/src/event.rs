@@ -0,0 +1,177 @@
+use auction_client::xdr::{ContractEvent, ContractEventBody, Limits, ReadXdr, ScAddress, ScVal};
+use auction_client::{field, BidInfo, Data, EscrowTerms, FromScVal, PendingSale, SaleRecord};
+use serde::Deserialize;
+
+use crate::Error;
+
+/// A contract event as exported from RPC or a test environment, before it is decoded
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawEvent {
+    /// Strkey of the contract that published the event
+    pub contract: String,
+    pub ledger: Option<u32>,
+    /// Unique id assigned by RPC, used to skip events that were already ingested
+    pub id: Option<String>,
+    pub topics: Vec<ScVal>,
+    pub value: ScVal,
+}
+
+/// An event as returned by the `getEvents` method of Soroban RPC, with the topics and value as base64 XDR
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcEvent {
+    contract_id: String,
+    ledger: Option<u32>,
+    id: Option<String>,
+    topic: Vec<String>,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RpcEvents {
+    Events(Vec<RpcEvent>),
+    Result { events: Vec<RpcEvent> },
+}
+
+impl RawEvent {
+    /// Parse events exported from Soroban RPC: an array of events, or the result of `getEvents`
+    pub fn from_json(json: &str) -> Result<Vec<RawEvent>, Error> {
+        let events = match serde_json::from_str(json)? {
+            RpcEvents::Events(events) => events,
+            RpcEvents::Result { events } => events,
+        };
+        events.into_iter().map(|event| {
+            let topics = event.topic.iter()
+                                    .map(|topic| ScVal::from_xdr_base64(topic, Limits::none()))
+                                    .collect::<Result<Vec<_>, _>>()?;
+            Ok(RawEvent {
+                contract: event.contract_id,
+                ledger: event.ledger,
+                id: event.id,
+                topics,
+                value: ScVal::from_xdr_base64(&event.value, Limits::none())?,
+            })
+        }).collect()
+    }
+
+    /// Parse one base64 `ContractEvent` per line. These events carry no ledger or id
+    pub fn from_xdr_lines(lines: &str) -> Result<Vec<RawEvent>, Error> {
+        lines.lines().filter(|line| !line.trim().is_empty()).map(|line| {
+            let event = ContractEvent::from_xdr_base64(line.trim(), Limits::none())?;
+            let contract = event.contract_id.map(|id| ScAddress::Contract(id).to_string()).unwrap_or_default();
+            let ContractEventBody::V0(body) = event.body;
+            Ok(RawEvent { contract, ledger: None, id: None, topics: body.topics.to_vec(), value: body.data })
+        }).collect()
+    }
+
+    /// Parse either format, JSON when the input starts with `[` or `{`
+    pub fn parse(input: &str) -> Result<Vec<RawEvent>, Error> {
+        match input.trim_start().chars().next() {
+            Some('[') | Some('{') => RawEvent::from_json(input),
+            _ => RawEvent::from_xdr_lines(input),
+        }
+    }
+
+    /// The name of the event, its first topic
+    pub fn name(&self) -> Option<String> {
+        match self.topics.first() {
+            Some(ScVal::Symbol(name)) => name.to_utf8_string().ok(),
+            _ => None,
+        }
+    }
+
+    /// The auction the event concerns. Contracts from before multiple auctions publish events without an id,
+    /// their auction is indexed as id 0
+    pub fn auction_id(&self) -> Option<u32> {
+        match self.topics.get(1) {
+            Some(ScVal::U32(id)) => Some(*id),
+            Some(_) => None,
+            None => Some(0),
+        }
+    }
+}
+
+/// The events that change the indexed state of an auction
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuctionEvent {
+    /// The auction data, and the counter token the escrow received
+    Created { data: Data, received: i128 },
+    Escrow(EscrowTerms),
+    /// The amended auction data, and the counter token received to top up the escrow
+    Amended { data: Data, received: i128 },
+    BidInfo(BidInfo),
+    Paused { paused_ledger: u32 },
+    Resumed { auction_start_ledger: u32 },
+    Pending { pending_sale: PendingSale, token_received: i128 },
+    Disputed(PendingSale),
+    Refunded(PendingSale),
+    Sold { sale: SaleRecord, token_received: i128 },
+    /// A sale published before sales were recorded, with only the price
+    Fulfilled { price: i128 },
+    Cancel { cancel_ledger: u32 },
+    Aborted { refund: i128 },
+    Closed { token_amount: i128, counter_token_amount: i128 },
+    Reset,
+}
+
+impl AuctionEvent {
+    /// Decode an auction event. Admin events, and events this indexer does not know yet, give `None`
+    pub fn decode(event: &RawEvent) -> Result<Option<AuctionEvent>, Error> {
+        let Some(name) = event.name() else {
+            return Ok(None)
+        };
+        // Events from before the versioned schema carry the payload itself
+        let legacy = event.topics.len() == 1;
+        let value = &event.value;
+        let decoded = match name.as_str() {
+            // The first contract versions always received the maximum bid, and the single token of a sale
+            "auction_data" => {
+                let data = legacy_data(value)?;
+                AuctionEvent::Created { received: data.bid_max_amount, data }
+            }
+            "fulfilled" => AuctionEvent::Fulfilled { price: i128::from_scval(value)? },
+            "bid_info" if legacy => AuctionEvent::BidInfo(BidInfo::from_scval(value)?),
+            _ if legacy => return Ok(None),
+            "created" => AuctionEvent::Created { data: field(value, "data")?, received: field(value, "received")? },
+            "escrow" => AuctionEvent::Escrow(field(value, "escrow")?),
+            "amended" => AuctionEvent::Amended { data: field(value, "data")?, received: field(value, "received")? },
+            "bid_info" => AuctionEvent::BidInfo(field(value, "bid_info")?),
+            "paused" => AuctionEvent::Paused { paused_ledger: field(value, "paused_ledger")? },
+            "resumed" => AuctionEvent::Resumed { auction_start_ledger: field(value, "auction_start_ledger")? },
+            "pending" => AuctionEvent::Pending {
+                pending_sale: field(value, "pending_sale")?,
+                token_received: field(value, "token_received")?,
+            },
+            "disputed" => AuctionEvent::Disputed(field(value, "pending_sale")?),
+            "refunded" => AuctionEvent::Refunded(field(value, "pending_sale")?),
+            "sold" => AuctionEvent::Sold { sale: field(value, "sale")?, token_received: field(value, "token_received")? },
+            "cancel" => AuctionEvent::Cancel { cancel_ledger: field(value, "cancel_ledger")? },
+            "aborted" => AuctionEvent::Aborted { refund: field(value, "refund")? },
+            "closed" => AuctionEvent::Closed {
+                token_amount: field(value, "token_amount")?,
+                counter_token_amount: field(value, "counter_token_amount")?,
+            },
+            "reset" => AuctionEvent::Reset,
+            _ => return Ok(None),
+        };
+        Ok(Some(decoded))
+    }
+}
+
+/// The auction data of the first contract versions, which called the buyer the host and had no cancel notice
+fn legacy_data(value: &ScVal) -> Result<Data, Error> {
+    let buyer = field(value, "buyer").or_else(|_| field(value, "host"))?;
+    Ok(Data {
+        buyer,
+        token: field(value, "token")?,
+        counter_token: field(value, "counter_token")?,
+        auction_start_ledger: field(value, "auction_start_ledger")?,
+        bid_start_amount: field(value, "bid_start_amount")?,
+        bid_incr_amount: field(value, "bid_incr_amount")?,
+        bid_incr_interval: field(value, "bid_incr_interval")?,
+        bid_incr_times: field(value, "bid_incr_times")?,
+        bid_max_amount: field(value, "bid_max_amount")?,
+        cancel_notice: field(value, "cancel_notice").unwrap_or(0),
+    })
+}
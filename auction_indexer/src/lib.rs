@@ -0,0 +1,61 @@
+//! Reconstruct reverse Dutch auctions from the events the contract publishes.
+//!
+//! Events are read as exported by Soroban RPC's `getEvents`, or as one base64 `ContractEvent` per line,
+//! and replayed in order into an embedded SQLite database: every auction with its data, state, sale, refund and the balances the contract holds for it,
+//! and every event that was ingested. Events this version does not know are stored but not applied,
+//! so they can be replayed by a later version.
+use std::fmt;
+
+pub use crate::event::{AuctionEvent, RawEvent};
+pub use crate::store::{Auction, Filter, Ingested, Store, StoredEvent};
+
+pub mod event;
+pub mod store;
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    Xdr(auction_client::xdr::Error),
+    Decode(auction_client::DecodeError),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Json(err) => write!(f, "Invalid JSON: {err}"),
+            Error::Xdr(err) => write!(f, "Invalid XDR: {err}"),
+            Error::Decode(err) => write!(f, "Invalid event: {err}"),
+            Error::Sqlite(err) => write!(f, "Database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<auction_client::xdr::Error> for Error {
+    fn from(err: auction_client::xdr::Error) -> Self {
+        Error::Xdr(err)
+    }
+}
+
+impl From<auction_client::DecodeError> for Error {
+    fn from(err: auction_client::DecodeError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,107 @@
+//! Index the events of reverse Dutch auction contracts into an SQLite database, and query it.
+//!
+//! `auction-indexer ingest` reads events exported from Soroban RPC, or one base64 `ContractEvent` per line.
+//! `auction-indexer auctions` lists the auctions, `auction-indexer show` shows one with its events.
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use auction_client::State;
+use auction_indexer::{Filter, RawEvent, Store};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "auction-indexer", version, about = "Index the events of reverse Dutch auctions into SQLite")]
+struct Cli {
+    /// SQLite database, created if it does not exist
+    #[arg(long, default_value = "auctions.sqlite")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Ingest event files, as JSON from Soroban RPC or as base64 XDR lines
+    Ingest {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// List the auctions
+    Auctions {
+        #[arg(long)]
+        contract: Option<String>,
+        #[arg(long)]
+        buyer: Option<String>,
+        /// Only auctions in this state, e.g. Running or Fulfilled
+        #[arg(long, value_parser = parse_state)]
+        state: Option<State>,
+    },
+    /// Show an auction and its events
+    Show {
+        #[arg(long)]
+        contract: String,
+        #[arg(long)]
+        id: u32,
+    },
+}
+
+fn parse_state(name: &str) -> Result<State, String> {
+    State::ALL.iter()
+              .find(|state| state.name().eq_ignore_ascii_case(name))
+              .copied()
+              .ok_or_else(|| format!("Unknown state {name}"))
+}
+
+fn run(cli: Cli, store: &mut Store) -> Result<String, String> {
+    match cli.command {
+        Command::Ingest { files } => {
+            let mut output = String::new();
+            for file in files {
+                let input = fs::read_to_string(&file).map_err(|err| format!("Can not read {}: {err}", file.display()))?;
+                let events = RawEvent::parse(&input).map_err(|err| format!("{}: {err}", file.display()))?;
+                let ingested = store.ingest(&events).map_err(|err| err.to_string())?;
+                output += &format!("{}: {} events stored, {} applied, {} already ingested\n",
+                                   file.display(), ingested.stored, ingested.applied, ingested.duplicates);
+            }
+            Ok(output)
+        }
+        Command::Auctions { contract, buyer, state } => {
+            let auctions = store.auctions(&Filter { contract, buyer, state }).map_err(|err| err.to_string())?;
+            let mut output = format!("{:<56}  {:>6}  {:<14}  {:>16}\n", "contract", "id", "state", "sale price");
+            for auction in auctions {
+                let price = auction.sale_price.map(|price| price.to_string()).unwrap_or_default();
+                output += &format!("{:<56}  {:>6}  {:<14}  {:>16}\n", auction.contract, auction.id, auction.state.name(), price);
+            }
+            Ok(output)
+        }
+        Command::Show { contract, id } => {
+            let auction = store.auction(&contract, id)
+                               .map_err(|err| err.to_string())?
+                               .ok_or_else(|| format!("No auction {id} of {contract}"))?;
+            let mut output = auction.to_string();
+            output += "  Events:\n";
+            for event in store.events(&contract, id).map_err(|err| err.to_string())? {
+                let ledger = event.ledger.map(|ledger| format!("ledger {ledger}")).unwrap_or_default();
+                output += &format!("    {:<10} {}{}\n", event.name, ledger, if event.applied { "" } else { " (not applied)" });
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = Store::open(&cli.db).map_err(|err| err.to_string())
+                                     .and_then(|mut store| run(cli, &mut store));
+    match result {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt;
+use std::path::Path;
+
+use auction_client::xdr::{Limits, WriteXdr};
+use auction_client::{Data, State};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+use crate::event::{AuctionEvent, RawEvent};
+use crate::Error;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id TEXT UNIQUE,
+    contract TEXT NOT NULL,
+    auction_id INTEGER,
+    name TEXT NOT NULL,
+    ledger INTEGER,
+    applied INTEGER NOT NULL,
+    topics TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS auctions (
+    contract TEXT NOT NULL,
+    auction_id INTEGER NOT NULL,
+    buyer TEXT NOT NULL,
+    token TEXT NOT NULL,
+    counter_token TEXT NOT NULL,
+    start_ledger INTEGER NOT NULL,
+    start_bid TEXT NOT NULL,
+    incr_amount TEXT NOT NULL,
+    incr_interval INTEGER NOT NULL,
+    incr_times INTEGER NOT NULL,
+    max_bid TEXT NOT NULL,
+    cancel_notice INTEGER NOT NULL,
+    arbiter TEXT,
+    state TEXT NOT NULL,
+    seller TEXT,
+    sale_price TEXT,
+    sale_ledger INTEGER,
+    refund TEXT,
+    counter_token_balance TEXT NOT NULL DEFAULT '0',
+    token_balance TEXT NOT NULL DEFAULT '0',
+    reset INTEGER NOT NULL DEFAULT 0,
+    created_ledger INTEGER,
+    updated_ledger INTEGER,
+    PRIMARY KEY (contract, auction_id)
+);
+CREATE INDEX IF NOT EXISTS auctions_buyer ON auctions (buyer);
+CREATE INDEX IF NOT EXISTS events_auction ON events (contract, auction_id);
+";
+
+/// An auction as reconstructed from its events. Amounts are stored as text, as SQLite integers are 64 bits
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Auction {
+    pub contract: String,
+    pub id: u32,
+    pub buyer: String,
+    pub token: String,
+    pub counter_token: String,
+    pub start_ledger: u32,
+    pub start_bid: i128,
+    pub incr_amount: i128,
+    pub incr_interval: u32,
+    pub incr_times: u32,
+    pub max_bid: i128,
+    pub cancel_notice: u32,
+    pub arbiter: Option<String>,
+    pub state: State,
+    pub seller: Option<String>,
+    pub sale_price: Option<i128>,
+    pub sale_ledger: Option<u32>,
+    /// Counter token returned to the buyer when the auction was aborted
+    pub refund: Option<i128>,
+    /// Counter token the contract holds for the auction
+    pub counter_token_balance: i128,
+    /// Auction token the contract holds for the auction
+    pub token_balance: i128,
+    /// The buyer reset the auction, which moved it into the contract's history
+    pub reset: bool,
+}
+
+/// An ingested event
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StoredEvent {
+    pub seq: u32,
+    pub name: String,
+    pub ledger: Option<u32>,
+    /// Whether the event changed the state of the auction
+    pub applied: bool,
+}
+
+/// What an ingest did
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Ingested {
+    pub stored: usize,
+    pub applied: usize,
+    /// Events that were already ingested, recognised by their RPC id
+    pub duplicates: usize,
+}
+
+/// Which auctions to list
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Filter {
+    pub contract: Option<String>,
+    pub buyer: Option<String>,
+    pub state: Option<State>,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Store, Error> {
+        Store::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Store, Error> {
+        Store::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Store, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    /// Store the events and replay them in order, in a single transaction
+    pub fn ingest(&mut self, events: &[RawEvent]) -> Result<Ingested, Error> {
+        let tx = self.conn.transaction()?;
+        let mut ingested = Ingested::default();
+        for event in events {
+            if let Some(id) = &event.id {
+                let seen: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM events WHERE event_id = ?1)", [id], |row| row.get(0))?;
+                if seen {
+                    ingested.duplicates += 1;
+                    continue
+                }
+            }
+            let decoded = match event.auction_id() {
+                Some(id) => AuctionEvent::decode(event)?.map(|decoded| (id, decoded)),
+                None => None,
+            };
+            let applied = match &decoded {
+                Some((id, decoded)) => apply(&tx, &event.contract, *id, event.ledger, decoded)?,
+                None => false,
+            };
+            let topics = event.topics.iter()
+                                     .map(|topic| topic.to_xdr_base64(Limits::none()))
+                                     .collect::<Result<Vec<_>, _>>()?
+                                     .join(" ");
+            tx.execute("INSERT INTO events (event_id, contract, auction_id, name, ledger, applied, topics, value)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                       params![event.id, event.contract, event.auction_id(), event.name().unwrap_or_default(),
+                               event.ledger, applied, topics, event.value.to_xdr_base64(Limits::none())?])?;
+            ingested.stored += 1;
+            ingested.applied += applied as usize;
+        }
+        tx.commit()?;
+        Ok(ingested)
+    }
+
+    pub fn auction(&self, contract: &str, id: u32) -> Result<Option<Auction>, Error> {
+        let auction = self.conn.query_row("SELECT * FROM auctions WHERE contract = ?1 AND auction_id = ?2",
+                                          params![contract, id], auction_from_row).optional()?;
+        Ok(auction)
+    }
+
+    /// The auctions matching the filter, by contract and id
+    pub fn auctions(&self, filter: &Filter) -> Result<Vec<Auction>, Error> {
+        let mut statement = self.conn.prepare("SELECT * FROM auctions
+                                               WHERE (?1 IS NULL OR contract = ?1) AND (?2 IS NULL OR buyer = ?2) AND (?3 IS NULL OR state = ?3)
+                                               ORDER BY contract, auction_id")?;
+        let state = filter.state.map(|state| state.name());
+        let auctions = statement.query_map(params![filter.contract, filter.buyer, state], auction_from_row)?
+                                .collect::<Result<Vec<_>, _>>()?;
+        Ok(auctions)
+    }
+
+    /// The events of an auction, in the order they were ingested
+    pub fn events(&self, contract: &str, id: u32) -> Result<Vec<StoredEvent>, Error> {
+        let mut statement = self.conn.prepare("SELECT seq, name, ledger, applied FROM events
+                                               WHERE contract = ?1 AND auction_id = ?2 ORDER BY seq")?;
+        let events = statement.query_map(params![contract, id], |row| Ok(StoredEvent {
+            seq: row.get(0)?,
+            name: row.get(1)?,
+            ledger: row.get(2)?,
+            applied: row.get(3)?,
+        }))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+}
+
+/// Apply an event to the auction it concerns. Returns whether an auction was changed
+fn apply(tx: &Transaction, contract: &str, id: u32, ledger: Option<u32>, event: &AuctionEvent) -> Result<bool, Error> {
+    let key = params![contract, id, ledger];
+    let changed = match event {
+        AuctionEvent::Created { data, received } => {
+            let state = match ledger {
+                Some(ledger) if ledger < data.auction_start_ledger => State::NotStarted,
+                _ => State::Running,
+            };
+            tx.execute("INSERT OR REPLACE INTO auctions (contract, auction_id, buyer, token, counter_token, start_ledger, start_bid,
+                                                         incr_amount, incr_interval, incr_times, max_bid, cancel_notice, state,
+                                                         counter_token_balance, created_ledger, updated_ledger)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?15)",
+                       params![contract, id, data.buyer.to_string(), data.token.to_string(), data.counter_token.to_string(),
+                               data.auction_start_ledger, data.bid_start_amount.to_string(), data.bid_incr_amount.to_string(),
+                               data.bid_incr_interval, data.bid_incr_times, data.bid_max_amount.to_string(), data.cancel_notice,
+                               state.name(), received.to_string(), ledger])?
+        }
+        AuctionEvent::Escrow(escrow) => {
+            tx.execute("UPDATE auctions SET arbiter = ?4, updated_ledger = ?3 WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, escrow.arbiter.to_string()])?
+        }
+        AuctionEvent::Amended { data, received } => amend(tx, contract, id, ledger, data)? + add_balances(tx, contract, id, *received, 0)?,
+        AuctionEvent::BidInfo(bid_info) => {
+            // The bid info is a view, it only tells whether an auction that has not ended has started
+            tx.execute("UPDATE auctions SET state = ?4, updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2 AND state IN ('NotStarted', 'Running')",
+                       params![contract, id, ledger, bid_info.state.name()])?
+        }
+        AuctionEvent::Paused { .. } => set_state(tx, key, State::Paused)?,
+        AuctionEvent::Resumed { auction_start_ledger } => {
+            tx.execute("UPDATE auctions SET state = 'Running', start_ledger = ?4, updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, auction_start_ledger])?
+        }
+        AuctionEvent::Pending { pending_sale, token_received } => {
+            tx.execute("UPDATE auctions SET state = 'PendingRelease', seller = ?4, sale_price = ?5, updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, pending_sale.seller.to_string(), pending_sale.price.to_string()])?
+                + add_balances(tx, contract, id, 0, *token_received)?
+        }
+        AuctionEvent::Disputed(_) => set_state(tx, key, State::Disputed)?,
+        AuctionEvent::Refunded(_) => {
+            tx.execute("UPDATE auctions SET state = 'Aborted', seller = NULL, sale_price = NULL, counter_token_balance = '0',
+                                            token_balance = '0', updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       key)?
+        }
+        AuctionEvent::Sold { sale, token_received } => {
+            tx.execute("UPDATE auctions SET state = 'Fulfilled', seller = ?4, sale_price = ?5, sale_ledger = ?6, updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, sale.seller.to_string(), sale.price.to_string(), sale.ledger])?
+                + add_balances(tx, contract, id, -sale.price, *token_received)?
+        }
+        AuctionEvent::Fulfilled { price } => {
+            tx.execute("UPDATE auctions SET state = 'Fulfilled', sale_price = ?4, sale_ledger = ?3, updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, price.to_string()])?
+                + add_balances(tx, contract, id, -price, 1)?
+        }
+        AuctionEvent::Cancel { .. } => set_state(tx, key, State::Cancelling)?,
+        AuctionEvent::Aborted { refund } => {
+            tx.execute("UPDATE auctions SET state = 'Aborted', refund = ?4, counter_token_balance = '0', token_balance = '0', updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       params![contract, id, ledger, refund.to_string()])?
+        }
+        AuctionEvent::Closed { .. } => {
+            tx.execute("UPDATE auctions SET state = 'Closed', counter_token_balance = '0', token_balance = '0', updated_ledger = ?3
+                        WHERE contract = ?1 AND auction_id = ?2",
+                       key)?
+        }
+        AuctionEvent::Reset => {
+            tx.execute("UPDATE auctions SET reset = 1, updated_ledger = ?3 WHERE contract = ?1 AND auction_id = ?2", key)?
+        }
+    };
+    Ok(changed > 0)
+}
+
+fn set_state(tx: &Transaction, key: &[&dyn rusqlite::ToSql], state: State) -> Result<usize, Error> {
+    let changed = tx.execute(&format!("UPDATE auctions SET state = '{}', updated_ledger = ?3 WHERE contract = ?1 AND auction_id = ?2",
+                                      state.name()),
+                             key)?;
+    Ok(changed)
+}
+
+fn amend(tx: &Transaction, contract: &str, id: u32, ledger: Option<u32>, data: &Data) -> Result<usize, Error> {
+    let changed = tx.execute("UPDATE auctions SET start_ledger = ?4, start_bid = ?5, incr_amount = ?6, incr_interval = ?7,
+                                                  incr_times = ?8, max_bid = ?9, updated_ledger = ?3
+                              WHERE contract = ?1 AND auction_id = ?2",
+                             params![contract, id, ledger, data.auction_start_ledger, data.bid_start_amount.to_string(),
+                                     data.bid_incr_amount.to_string(), data.bid_incr_interval, data.bid_incr_times,
+                                     data.bid_max_amount.to_string()])?;
+    Ok(changed)
+}
+
+/// Add the amounts the contract received, or paid out when negative, to the balances it holds for an auction
+fn add_balances(tx: &Transaction, contract: &str, id: u32, counter_token: i128, token: i128) -> Result<usize, Error> {
+    let balances = tx.query_row("SELECT counter_token_balance, token_balance FROM auctions WHERE contract = ?1 AND auction_id = ?2",
+                                params![contract, id],
+                                |row| Ok((amount(row, "counter_token_balance")?, amount(row, "token_balance")?))).optional()?;
+    let Some((counter_token_balance, token_balance)) = balances else {
+        return Ok(0)
+    };
+    let changed = tx.execute("UPDATE auctions SET counter_token_balance = ?3, token_balance = ?4 WHERE contract = ?1 AND auction_id = ?2",
+                             params![contract, id, (counter_token_balance + counter_token).to_string(), (token_balance + token).to_string()])?;
+    Ok(changed)
+}
+
+fn amount(row: &Row, column: &str) -> rusqlite::Result<i128> {
+    let text: String = row.get(column)?;
+    text.parse().map_err(|_| rusqlite::Error::InvalidColumnType(0, column.into(), rusqlite::types::Type::Text))
+}
+
+fn optional_amount(row: &Row, column: &str) -> rusqlite::Result<Option<i128>> {
+    let text: Option<String> = row.get(column)?;
+    match text {
+        Some(_) => amount(row, column).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn auction_from_row(row: &Row) -> rusqlite::Result<Auction> {
+    let state: String = row.get("state")?;
+    let state = State::ALL.iter()
+                          .find(|variant| variant.name() == state)
+                          .copied()
+                          .ok_or_else(|| rusqlite::Error::InvalidColumnType(0, "state".into(), rusqlite::types::Type::Text))?;
+    Ok(Auction {
+        contract: row.get("contract")?,
+        id: row.get("auction_id")?,
+        buyer: row.get("buyer")?,
+        token: row.get("token")?,
+        counter_token: row.get("counter_token")?,
+        start_ledger: row.get("start_ledger")?,
+        start_bid: amount(row, "start_bid")?,
+        incr_amount: amount(row, "incr_amount")?,
+        incr_interval: row.get("incr_interval")?,
+        incr_times: row.get("incr_times")?,
+        max_bid: amount(row, "max_bid")?,
+        cancel_notice: row.get("cancel_notice")?,
+        arbiter: row.get("arbiter")?,
+        state,
+        seller: row.get("seller")?,
+        sale_price: optional_amount(row, "sale_price")?,
+        sale_ledger: row.get("sale_ledger")?,
+        refund: optional_amount(row, "refund")?,
+        counter_token_balance: amount(row, "counter_token_balance")?,
+        token_balance: amount(row, "token_balance")?,
+        reset: row.get("reset")?,
+    })
+}
+
+impl fmt::Display for Auction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Auction {} of {}", self.id, self.contract)?;
+        writeln!(f, "  Buyer:          {}", self.buyer)?;
+        writeln!(f, "  Token:          {}", self.token)?;
+        writeln!(f, "  Counter token:  {}", self.counter_token)?;
+        writeln!(f, "  Schedule:       {} + {} every {} ledgers, {} times, from ledger {}",
+                 self.start_bid, self.incr_amount, self.incr_interval, self.incr_times, self.start_ledger)?;
+        writeln!(f, "  Maximum bid:    {}", self.max_bid)?;
+        if let Some(arbiter) = &self.arbiter {
+            writeln!(f, "  Arbiter:        {arbiter}")?;
+        }
+        writeln!(f, "  State:          {}{}", self.state.name(), if self.reset { ", reset" } else { "" })?;
+        if let Some(price) = self.sale_price {
+            writeln!(f, "  Sale price:     {price}")?;
+        }
+        if let Some(seller) = &self.seller {
+            writeln!(f, "  Seller:         {seller}")?;
+        }
+        if let Some(refund) = self.refund {
+            writeln!(f, "  Refunded:       {refund}")?;
+        }
+        writeln!(f, "  Held:           {} counter token, {} token", self.counter_token_balance, self.token_balance)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+use auction_client::xdr::{ContractEvent, ContractEventBody, ContractEventType, ContractEventV0, ExtensionPoint, Hash,
+                          Limits, ScAddress, ScVal, WriteXdr};
+use auction_client::{State, ToScVal};
+use soroban_auction::types::Status;
+use soroban_auction::{AuctionContract, AuctionContractClient};
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Events, Ledger};
+use soroban_sdk::{token, Address, Env, TryFromVal};
+
+use crate::{AuctionEvent, Filter, RawEvent, Store};
+
+/// Events published by the contract, with the ledger they were published in
+struct Export {
+    events: Vec<(u32, ScAddress, Vec<ScVal>, ScVal)>,
+    seen: u32,
+}
+
+impl Export {
+    /// Collect the events published since the last call, which the test env keeps for the whole test
+    fn collect(&mut self, env: &Env) {
+        let all = env.events().all();
+        for (contract, topics, value) in all.iter().skip(self.seen as usize) {
+            let topics = topics.iter().map(|topic| ScVal::try_from_val(env, &topic).unwrap()).collect();
+            self.events.push((env.ledger().sequence(), ScAddress::try_from(&contract).unwrap(), topics,
+                              ScVal::try_from_val(env, &value).unwrap()));
+        }
+        self.seen = all.len();
+    }
+
+    /// The events as returned by Soroban RPC's `getEvents`
+    fn to_json(&self) -> String {
+        let events: Vec<serde_json::Value> = self.events.iter().enumerate().map(|(index, (ledger, contract, topics, value))| {
+            serde_json::json!({
+                "type": "contract",
+                "ledger": ledger,
+                "contractId": contract.to_string(),
+                "id": format!("{ledger:019}-{index:010}"),
+                "topic": topics.iter().map(|topic| topic.to_xdr_base64(Limits::none()).unwrap()).collect::<Vec<_>>(),
+                "value": value.to_xdr_base64(Limits::none()).unwrap(),
+            })
+        }).collect();
+        serde_json::json!({ "events": events, "latestLedger": 1000 }).to_string()
+    }
+
+    /// The events as base64 `ContractEvent` lines, without ledgers
+    fn to_xdr_lines(&self) -> String {
+        self.events.iter().map(|(_, contract, topics, value)| {
+            let ScAddress::Contract(contract_id) = contract else { panic!("Not a contract") };
+            let event = ContractEvent {
+                ext: ExtensionPoint::V0,
+                contract_id: Some(contract_id.clone()),
+                type_: ContractEventType::Contract,
+                body: ContractEventBody::V0(ContractEventV0 { topics: topics.clone().try_into().unwrap(), data: value.clone() }),
+            };
+            event.to_xdr_base64(Limits::none()).unwrap() + "\n"
+        }).collect()
+    }
+}
+
+/// Run two auctions on the contract: one that is sold, closed and reset, and one that is amended, paused and aborted
+fn run_auctions() -> (Export, String, String) {
+    // The export holds the events, so the environment is not written as a snapshot
+    let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.sequence_number = 50);
+    let mut export = Export { events: Vec::new(), seen: 0 };
+
+    let buyer = Address::generate(&env);
+    let seller = Address::generate(&env);
+    let auction_token = env.register_stellar_asset_contract(Address::generate(&env));
+    let counter_token = env.register_stellar_asset_contract(Address::generate(&env));
+    token::StellarAssetClient::new(&env, &auction_token).mint(&seller, &1);
+    token::StellarAssetClient::new(&env, &counter_token).mint(&buyer, &10_000);
+    let contract_id = env.register_contract(None, AuctionContract);
+    let client = AuctionContractClient::new(&env, &contract_id);
+    export.collect(&env);
+    export.events.clear();
+
+    let setup = |start_ledger: Option<u32>| client.setup_auction(&buyer, &auction_token, &counter_token, &1000, &100, &10, &10,
+                                                                  &None, &start_ledger, &0);
+    assert_eq!(setup(None), Status::Started);
+    client.get_bid_info();
+    export.collect(&env);
+
+    env.ledger().with_mut(|li| li.sequence_number = 75);
+    assert_eq!(client.sell_token(&seller, &0, &None), Status::Fulfilled);
+    assert_eq!(client.close_auction(), Status::Closed);
+    assert_eq!(client.reset_auction(), Status::Reset);
+    export.collect(&env);
+
+    env.ledger().with_mut(|li| li.sequence_number = 100);
+    assert_eq!(setup(Some(200)), Status::Scheduled);
+    assert_eq!(client.amend_auction(&buyer, &1000, &100, &1), Status::Amended);
+    export.collect(&env);
+    env.ledger().with_mut(|li| li.sequence_number = 250);
+    client.get_bid_info();
+    export.collect(&env);
+    env.ledger().with_mut(|li| li.sequence_number = 260);
+    assert_eq!(client.pause_auction(&buyer), Status::Paused);
+    export.collect(&env);
+    env.ledger().with_mut(|li| li.sequence_number = 270);
+    assert_eq!(client.resume_auction(&buyer), Status::Resumed);
+    export.collect(&env);
+    assert_eq!(client.close_auction(), Status::Aborted);
+    export.collect(&env);
+
+    let address = |address: &Address| ScAddress::try_from(address).unwrap().to_string();
+    (export, address(&contract_id), address(&seller))
+}
+
+#[test]
+fn test_ingest_json(){
+    let (export, contract, seller) = run_auctions();
+    let json = export.to_json();
+    let events = RawEvent::parse(&json).unwrap();
+    assert_eq!(events.len(), export.events.len());
+
+    let mut store = Store::open_in_memory().unwrap();
+    let ingested = store.ingest(&events).unwrap();
+    assert_eq!((ingested.stored, ingested.duplicates), (events.len(), 0));
+
+    // Ingesting the same export again changes nothing
+    let again = store.ingest(&RawEvent::parse(&json).unwrap()).unwrap();
+    assert_eq!((again.stored, again.duplicates), (0, events.len()));
+
+    let sold = store.auction(&contract, 1).unwrap().unwrap();
+    assert_eq!((sold.state, sold.sale_price, sold.sale_ledger, sold.reset), (State::Closed, Some(1200), Some(75), true));
+    assert_eq!(sold.seller, Some(seller));
+    assert_eq!((sold.start_bid, sold.max_bid), (1000, 2000));
+    assert_eq!((sold.counter_token_balance, sold.token_balance), (0, 0));
+
+    // The start is shifted by the ten ledgers the auction was paused
+    let aborted = store.auction(&contract, 2).unwrap().unwrap();
+    assert_eq!((aborted.state, aborted.sale_price, aborted.refund, aborted.start_ledger), (State::Aborted, None, Some(2100), 210));
+
+    let names: Vec<String> = store.events(&contract, 2).unwrap().into_iter().map(|event| event.name).collect();
+    assert_eq!(names, ["created", "amended", "bid_info", "paused", "resumed", "aborted"]);
+
+    // Before they end, the auctions hold what the events report the contract received
+    let mut partial = Store::open_in_memory().unwrap();
+    let sold_at = events.iter().position(|event| event.name().as_deref() == Some("sold")).unwrap();
+    partial.ingest(&events[..=sold_at]).unwrap();
+    let held = partial.auction(&contract, 1).unwrap().unwrap();
+    assert_eq!((held.state, held.counter_token_balance, held.token_balance), (State::Fulfilled, 800, 1));
+    let amended_at = events.iter().position(|event| event.name().as_deref() == Some("amended")).unwrap();
+    partial.ingest(&events[sold_at + 1..=amended_at]).unwrap();
+    let held = partial.auction(&contract, 2).unwrap().unwrap();
+    assert_eq!((held.max_bid, held.counter_token_balance, held.token_balance), (2100, 2100, 0));
+
+    let closed = store.auctions(&Filter { state: Some(State::Closed), ..Filter::default() }).unwrap();
+    assert_eq!(closed, vec![sold.clone()]);
+    let by_buyer = store.auctions(&Filter { buyer: Some(sold.buyer.clone()), ..Filter::default() }).unwrap();
+    assert_eq!(by_buyer, vec![sold, aborted]);
+    assert!(store.auctions(&Filter { buyer: Some(contract), ..Filter::default() }).unwrap().is_empty());
+}
+
+#[test]
+fn test_ingest_xdr(){
+    // Without ledgers the events still give the same outcome
+    let (export, _, _) = run_auctions();
+    let events = RawEvent::parse(&export.to_xdr_lines()).unwrap();
+    assert!(events.iter().all(|event| event.ledger.is_none()));
+
+    let mut store = Store::open_in_memory().unwrap();
+    store.ingest(&events).unwrap();
+    let auctions = store.auctions(&Filter::default()).unwrap();
+    let outcomes: Vec<_> = auctions.iter().map(|auction| (auction.id, auction.state, auction.sale_price)).collect();
+    assert_eq!(outcomes, vec![(1, State::Closed, Some(1200)), (2, State::Aborted, None)]);
+}
+
+#[test]
+fn test_legacy_and_unknown_events(){
+    // The first contract versions published the data with a host and no auction id, and the price when fulfilled
+    let contract = ScAddress::Contract(Hash([1; 32])).to_string();
+    let host = ScAddress::Contract(Hash([2; 32]));
+    let symbol = |name: &str| ScVal::Symbol(name.try_into().unwrap());
+    let field = |name: &str, val: ScVal| auction_client::xdr::ScMapEntry { key: symbol(name), val };
+    let data = ScVal::Map(Some(auction_client::xdr::ScMap(vec![
+        field("auction_start_ledger", 10u32.to_scval()),
+        field("bid_incr_amount", 5i128.to_scval()),
+        field("bid_incr_interval", 1u32.to_scval()),
+        field("bid_incr_times", 4u32.to_scval()),
+        field("bid_max_amount", 120i128.to_scval()),
+        field("bid_start_amount", 100i128.to_scval()),
+        field("counter_token", host.to_scval()),
+        field("host", host.to_scval()),
+        field("token", host.to_scval()),
+    ].try_into().unwrap())));
+    let event = |name: &str, topics: Vec<ScVal>, value: ScVal| RawEvent {
+        contract: contract.clone(), ledger: Some(12), id: None, topics: [vec![symbol(name)], topics].concat(), value,
+    };
+    let events = vec![
+        event("auction_data", vec![], data),
+        event("fulfilled", vec![], 110i128.to_scval()),
+        event("rebated", vec![0u32.to_scval(), host.to_scval()], ScVal::Void),
+    ];
+    assert_eq!(AuctionEvent::decode(&events[1]).unwrap(), Some(AuctionEvent::Fulfilled { price: 110 }));
+    assert_eq!(AuctionEvent::decode(&events[2]).unwrap(), None);
+
+    let mut store = Store::open_in_memory().unwrap();
+    let ingested = store.ingest(&events).unwrap();
+    assert_eq!((ingested.stored, ingested.applied), (3, 2));
+    let auction = store.auction(&contract, 0).unwrap().unwrap();
+    assert_eq!((auction.buyer, auction.state, auction.sale_price), (host.to_string(), State::Fulfilled, Some(110)));
+    assert_eq!((auction.counter_token_balance, auction.token_balance), (10, 1));
+
+    // Events this version does not know are kept, to be replayed by a later version
+    let stored = store.events(&contract, 0).unwrap();
+    assert_eq!(stored.iter().map(|event| (event.name.as_str(), event.applied)).collect::<Vec<_>>(),
+               vec![("auction_data", true), ("fulfilled", true), ("rebated", false)]);
+}
@@ -0,0 +1,137 @@
+#![no_std]
+//! Bid schedule of the reverse Dutch auction.
+//!
+//! The bid starts at `start_bid` on `start_ledger`, and increases by `incr_amount` every `incr_interval` ledgers,
+//! `incr_times` times, until it reaches the maximum bid. The contract, its tests and off-chain tools all
+//! calculate the bid with these functions, so their numbers can not diverge.
+
+/// The parameters that determine the bid schedule of an auction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScheduleParams {
+    pub start_ledger: u32,
+    pub start_bid: i128,
+    pub incr_amount: i128,
+    pub incr_interval: u32,
+    pub incr_times: u32,
+}
+
+/// The bid at a ledger, and how it changes next
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bid {
+    pub current_bid: i128,
+    pub next_bid: i128,
+    /// Ledger of the next increase, or 0 once the maximum bid is reached
+    pub next_bid_ledger: u32,
+    /// Ledgers until the next increase, or 0 once the maximum bid is reached
+    pub ledgers_to_next_increase: u32,
+    pub max_bid: i128,
+    pub max_bid_ledger: u32,
+}
+
+/// A single step of the schedule: the bid from `ledger` until the next step
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub ledger: u32,
+    pub bid: i128,
+}
+
+impl ScheduleParams {
+    /// The maximum bid, or None if it does not fit in an i128
+    pub fn max_bid(&self) -> Option<i128> {
+        self.incr_amount.checked_mul(self.incr_times as i128)
+            .and_then(|increase| increase.checked_add(self.start_bid))
+    }
+
+    /// The ledger at which the maximum bid is reached, or None if it does not fit in a u32
+    pub fn max_bid_ledger(&self) -> Option<u32> {
+        self.incr_times.checked_mul(self.incr_interval)
+            .and_then(|ledgers| ledgers.checked_add(self.start_ledger))
+    }
+
+    /// Number of ledgers from the start until the maximum bid is reached, or None if it does not fit in a u32
+    pub fn duration(&self) -> Option<u32> {
+        self.incr_times.checked_mul(self.incr_interval)
+    }
+
+    /// How often the bid has increased at the given ledger. Before the start, this is 0
+    ///
+    /// # Panics
+    /// If `incr_interval` is 0
+    pub fn times_increased(&self, ledger: u32) -> u32 {
+        (ledger.saturating_sub(self.start_ledger) / self.incr_interval).min(self.incr_times)
+    }
+}
+
+/// Calculate the bid at the given ledger. Before the start ledger, the starting bid is reported.
+///
+/// Ledgers and bids that do not fit their type saturate, so any ledger can be queried.
+///
+/// # Panics
+/// If `incr_interval` is 0, which the contract rejects when an auction is set up
+pub fn bid_at(params: &ScheduleParams, ledger: u32) -> Bid {
+    let max_bid_ledger: u32 = params.start_ledger.saturating_add(params.incr_interval.saturating_mul(params.incr_times));
+    let max_bid: i128 = params.start_bid.saturating_add(params.incr_amount.saturating_mul(params.incr_times as i128));
+
+    // If the bid has reached its maximum, report that maximum as upcoming bid
+    if ledger >= max_bid_ledger {
+        return Bid {
+            current_bid: max_bid,
+            next_bid: max_bid,
+            next_bid_ledger: 0,
+            ledgers_to_next_increase: 0,
+            max_bid,
+            max_bid_ledger,
+        }
+    }
+
+    // If the bid is not yet at it's maximum, report the current and upcoming price/bid info
+    let times_increased: u32 = params.times_increased(ledger);
+    let current_bid: i128 = params.start_bid.saturating_add(params.incr_amount.saturating_mul(times_increased as i128));
+    let next_bid_ledger: u32 = params.start_ledger.saturating_add((times_increased + 1).saturating_mul(params.incr_interval));
+    Bid {
+        current_bid,
+        next_bid: current_bid.saturating_add(params.incr_amount),
+        next_bid_ledger,
+        ledgers_to_next_increase: next_bid_ledger - ledger,
+        max_bid,
+        max_bid_ledger,
+    }
+}
+
+/// Iterate over every step of the schedule, from the starting bid at the start ledger to the maximum bid
+pub fn schedule(params: &ScheduleParams) -> Schedule {
+    Schedule { params: *params, step: 0 }
+}
+
+/// Iterator over the steps of a schedule, see `schedule`
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    params: ScheduleParams,
+    step: u64,
+}
+
+impl Iterator for Schedule {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        if self.step > self.params.incr_times as u64 {
+            return None
+        }
+        let step = Step {
+            ledger: self.params.start_ledger + (self.step as u32 * self.params.incr_interval),
+            bid: self.params.start_bid + (self.params.incr_amount * self.step as i128),
+        };
+        self.step += 1;
+        Some(step)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.params.incr_times as u64 + 1 - self.step) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Schedule {}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,130 @@
+extern crate std;
+use proptest::prelude::*;
+
+use crate::{bid_at, schedule, Bid, ScheduleParams, Step};
+
+const PARAMS: ScheduleParams = ScheduleParams {
+    start_ledger: 100,
+    start_bid: 1000,
+    incr_amount: 100,
+    incr_interval: 10,
+    incr_times: 10,
+};
+
+#[test]
+fn test_bid_at(){
+    // Before the start, the starting bid is reported
+    assert_eq!(bid_at(&PARAMS, 0), Bid {
+        current_bid: 1000,
+        next_bid: 1100,
+        next_bid_ledger: 110,
+        ledgers_to_next_increase: 110,
+        max_bid: 2000,
+        max_bid_ledger: 200,
+    });
+
+    // During the auction, the bid increases at the end of every interval
+    let bid = bid_at(&PARAMS, 109);
+    assert_eq!((bid.current_bid, bid.next_bid, bid.ledgers_to_next_increase), (1000, 1100, 1));
+    let bid = bid_at(&PARAMS, 110);
+    assert_eq!((bid.current_bid, bid.next_bid, bid.next_bid_ledger), (1100, 1200, 120));
+    assert_eq!(bid_at(&PARAMS, 199).current_bid, 1900);
+
+    // From the last increase onwards, the maximum bid is reported
+    for ledger in [200, 201, u32::MAX] {
+        assert_eq!(bid_at(&PARAMS, ledger), Bid {
+            current_bid: 2000,
+            next_bid: 2000,
+            next_bid_ledger: 0,
+            ledgers_to_next_increase: 0,
+            max_bid: 2000,
+            max_bid_ledger: 200,
+        });
+    }
+}
+
+#[test]
+fn test_schedule(){
+    let steps = schedule(&PARAMS);
+    assert_eq!(steps.len(), 11);
+    for (i, step) in steps.enumerate() {
+        assert_eq!(step, Step { ledger: 100 + 10 * i as u32, bid: 1000 + 100 * i as i128 });
+        assert_eq!(bid_at(&PARAMS, step.ledger).current_bid, step.bid);
+    }
+
+    // Without increases, the schedule is only the starting bid
+    let params = ScheduleParams { incr_times: 0, ..PARAMS };
+    assert_eq!(schedule(&params).last(), Some(Step { ledger: 100, bid: 1000 }));
+    assert_eq!(schedule(&params).count(), 1);
+}
+
+#[test]
+fn test_checked_params(){
+    assert_eq!(PARAMS.max_bid(), Some(2000));
+    assert_eq!(PARAMS.max_bid_ledger(), Some(200));
+    assert_eq!(PARAMS.duration(), Some(100));
+    assert_eq!(PARAMS.times_increased(50), 0);
+    assert_eq!(PARAMS.times_increased(155), 5);
+    assert_eq!(PARAMS.times_increased(5000), 10);
+
+    let params = ScheduleParams { incr_amount: i128::MAX, ..PARAMS };
+    assert_eq!(params.max_bid(), None);
+    let params = ScheduleParams { incr_interval: u32::MAX, ..PARAMS };
+    assert_eq!(params.max_bid_ledger(), None);
+    assert_eq!(params.duration(), None);
+    let params = ScheduleParams { start_ledger: u32::MAX - 50, ..PARAMS };
+    assert_eq!(params.max_bid_ledger(), None);
+}
+
+#[test]
+fn test_bid_at_saturates(){
+    // A schedule shifted past the last ledger reports its ledgers at u32::MAX, instead of overflowing
+    let params = ScheduleParams { start_ledger: u32::MAX - 50, ..PARAMS };
+    let bid = bid_at(&params, u32::MAX - 1);
+    assert_eq!((bid.current_bid, bid.next_bid_ledger, bid.ledgers_to_next_increase), (1400, u32::MAX, 1));
+    assert_eq!(bid_at(&params, u32::MAX).max_bid_ledger, u32::MAX);
+
+    // Bids saturate at i128::MAX
+    let params = ScheduleParams { incr_amount: i128::MAX, ..PARAMS };
+    assert_eq!(bid_at(&params, 110).next_bid, i128::MAX);
+    assert_eq!(bid_at(&params, 200).current_bid, i128::MAX);
+}
+
+/// Schedules that pass the contract's validation
+fn valid_params() -> impl Strategy<Value = ScheduleParams> {
+    (0..1_000_000u32, 1..1_000_000_000_000i128, 1..1_000_000_000i128, 1..10_000u32, 0..5_000u32)
+        .prop_map(|(start_ledger, start_bid, incr_amount, incr_interval, incr_times)| ScheduleParams {
+            start_ledger,
+            start_bid,
+            incr_amount,
+            incr_interval,
+            incr_times,
+        })
+}
+
+proptest! {
+    #[test]
+    fn prop_bid_monotonic_and_bounded(params in valid_params(), a in any::<u32>(), b in any::<u32>()){
+        let (early, late) = (bid_at(&params, a.min(b)), bid_at(&params, a.max(b)));
+        let max_bid = params.max_bid().unwrap();
+        prop_assert!(early.current_bid <= late.current_bid);
+        for bid in [early, late] {
+            prop_assert!(params.start_bid <= bid.current_bid && bid.current_bid <= bid.next_bid && bid.next_bid <= max_bid);
+            prop_assert_eq!((bid.max_bid, bid.max_bid_ledger), (max_bid, params.max_bid_ledger().unwrap()));
+        }
+    }
+
+    #[test]
+    fn prop_schedule_matches_bid_at(params in valid_params()){
+        let mut previous: Option<Step> = None;
+        for step in schedule(&params) {
+            prop_assert_eq!(bid_at(&params, step.ledger).current_bid, step.bid);
+            if let Some(previous) = previous {
+                prop_assert_eq!(step.ledger - previous.ledger, params.incr_interval);
+                prop_assert_eq!(step.bid - previous.bid, params.incr_amount);
+            }
+            previous = Some(step);
+        }
+        prop_assert_eq!(previous, Some(Step { ledger: params.max_bid_ledger().unwrap(), bid: params.max_bid().unwrap() }));
+    }
+}
@@ -0,0 +1,48 @@
+//! Fuzz the contract with random auction parameters and sequences of calls, checking its invariants after every call
+#![no_main]
+
+use arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use soroban_auction::testutils::{AuctionParams, Harness, Op};
+
+fn params(u: &mut Unstructured) -> Result<AuctionParams> {
+    Ok(AuctionParams {
+        start_bid: u.int_in_range(1..=1_000_000)?,
+        incr_amount: u.int_in_range(1..=10_000)?,
+        incr_times: u.int_in_range(1..=20)?,
+        incr_interval: u.int_in_range(1..=50)?,
+        start_delay: u.int_in_range(0..=100)?,
+        cancel_notice: u.int_in_range(0..=100)?,
+    })
+}
+
+fn op(u: &mut Unstructured) -> Result<Op> {
+    Ok(match u.int_in_range(0..=7)? {
+        0 => Op::Advance(u.int_in_range(1..=200)?),
+        1 => Op::Setup,
+        2 => Op::Sell,
+        3 => Op::Close,
+        4 => Op::Reset,
+        5 => Op::Pause,
+        6 => Op::Resume,
+        _ => Op::Amend {
+            bid_raise: u.int_in_range(0..=1000)?,
+            incr_raise: u.int_in_range(0..=100)?,
+            extra_incr_times: u.int_in_range(0..=5)?,
+        },
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let Ok(params) = params(&mut u) else { return };
+    let mut harness = Harness::new(params);
+    harness.apply(Op::Setup);
+    // Keep the sequences short, every call runs the contract in the test environment
+    for _ in 0..64 {
+        match op(&mut u) {
+            Ok(op) => { harness.apply(op); }
+            Err(_) => break,
+        }
+    }
+});
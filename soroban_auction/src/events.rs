@@ -0,0 +1,274 @@
+//! Events published by the auction contract.
+//!
+//! Every event that concerns an auction has the topics `(name, auction id, buyer)`,
+//! so indexers can follow a single auction or all auctions of a buyer.
+//! The admin events have the topics `(name, admin)`.
+//! Every payload carries the `version` of this schema, which is bumped whenever a topic or payload changes.
+//!
+//! | Name       | Payload             | Published when                                                     |
+//! |------------|---------------------|--------------------------------------------------------------------|
+//! | `admin`    | `AdminSetEvent`     | The admin is set by `initialize`                                   |
+//! | `rescued`  | `RescuedEvent`      | The admin rescues tokens sent to the contract by accident          |
+//! | `upgraded` | `UpgradedEvent`     | The admin upgrades the contract code                               |
+//! | `migrated` | `MigratedEvent`     | The admin migrates the storage to the current layout               |
+//! | `created`  | `CreatedEvent`      | An auction is set up                                               |
+//! | `escrow`   | `EscrowEvent`       | An auction is set up with escrow, right after `created`            |
+//! | `amended`  | `AmendedEvent`      | The buyer or its operator amends the auction                       |
+//! | `bid_info` | `BidInfoEvent`      | Somebody invokes `get_bid_info`                                    |
+//! | `paused`   | `PausedEvent`       | The buyer or its operator pauses the auction                       |
+//! | `resumed`  | `ResumedEvent`      | The buyer or its operator resumes the auction, with the shifted start ledger |
+//! | `operator` | `OperatorEvent`     | The buyer appoints an operator, or removes it (`appointed` is false) |
+//! | `pending`  | `PendingEvent`      | The token is sold and the payment is held in escrow                |
+//! | `disputed` | `DisputedEvent`     | The buyer disputes a pending sale                                  |
+//! | `refunded` | `RefundedEvent`     | The arbiter refunds the buyer, the auction ends as Aborted         |
+//! | `sold`     | `SoldEvent`         | The seller is paid, directly or after escrow, the auction is Fulfilled |
+//! | `cancel`   | `CancelEvent`       | The buyer closes the auction with a cancellation notice            |
+//! | `aborted`  | `AbortedEvent`      | The buyer closes the auction without a sale                        |
+//! | `closed`   | `ClosedEvent`       | The buyer closes a Fulfilled auction                               |
+//! | `reset`    | `ResetEvent`        | The buyer resets the auction, which moves it into the history      |
+//!
+//! Replaying `created`, `amended`, `paused`/`resumed`, `sold` and `aborted`/`closed` in order gives the full state of an auction.
+//! The amounts the contract actually received are part of `created`, `amended`, `pending` and `sold`, so the balances it holds follow too.
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env};
+
+use crate::types::{BidInfo, Data, EscrowTerms, PendingSale, SaleRecord};
+
+/// Version of the event schema
+pub const EVENT_VERSION: u32 = 1;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminSetEvent {
+    pub version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescuedEvent {
+    pub version: u32,
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub version: u32,
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub version: u32,
+    pub from_storage_version: u32,
+    pub to_storage_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreatedEvent {
+    pub version: u32,
+    pub data: Data,
+    /// Counter token received into the escrow
+    pub received: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowEvent {
+    pub version: u32,
+    pub escrow: EscrowTerms,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AmendedEvent {
+    pub version: u32,
+    pub data: Data,
+    /// Counter token received to top up the escrow, 0 if the maximum bid did not rise
+    pub received: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BidInfoEvent {
+    pub version: u32,
+    pub bid_info: BidInfo,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PausedEvent {
+    pub version: u32,
+    pub paused_ledger: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResumedEvent {
+    pub version: u32,
+    pub auction_start_ledger: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorEvent {
+    pub version: u32,
+    pub operator: Address,
+    pub appointed: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingEvent {
+    pub version: u32,
+    pub pending_sale: PendingSale,
+    /// Auction token received from the seller
+    pub token_received: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputedEvent {
+    pub version: u32,
+    pub pending_sale: PendingSale,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundedEvent {
+    pub version: u32,
+    pub pending_sale: PendingSale,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SoldEvent {
+    pub version: u32,
+    pub sale: SaleRecord,
+    /// Auction token received from the seller, 0 when it was already received with the pending sale
+    pub token_received: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CancelEvent {
+    pub version: u32,
+    pub cancel_ledger: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AbortedEvent {
+    pub version: u32,
+    pub refund: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClosedEvent {
+    pub version: u32,
+    pub token_amount: i128,
+    pub counter_token_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResetEvent {
+    pub version: u32,
+}
+
+pub(crate) fn admin_set(env: &Env, admin: &Address) {
+    env.events().publish((symbol_short!("admin"), admin.clone()), AdminSetEvent { version: EVENT_VERSION });
+}
+
+pub(crate) fn rescued(env: &Env, admin: &Address, token: &Address, to: &Address, amount: i128) {
+    let event = RescuedEvent { version: EVENT_VERSION, token: token.clone(), to: to.clone(), amount };
+    env.events().publish((symbol_short!("rescued"), admin.clone()), event);
+}
+
+pub(crate) fn upgraded(env: &Env, admin: &Address, wasm_hash: &BytesN<32>) {
+    let event = UpgradedEvent { version: EVENT_VERSION, wasm_hash: wasm_hash.clone() };
+    env.events().publish((symbol_short!("upgraded"), admin.clone()), event);
+}
+
+pub(crate) fn migrated(env: &Env, admin: &Address, from_storage_version: u32, to_storage_version: u32) {
+    let event = MigratedEvent { version: EVENT_VERSION, from_storage_version, to_storage_version };
+    env.events().publish((symbol_short!("migrated"), admin.clone()), event);
+}
+
+pub(crate) fn created(env: &Env, id: u32, data: &Data, received: i128) {
+    let event = CreatedEvent { version: EVENT_VERSION, data: data.clone(), received };
+    env.events().publish((symbol_short!("created"), id, data.buyer.clone()), event);
+}
+
+pub(crate) fn escrow(env: &Env, id: u32, buyer: &Address, escrow: &EscrowTerms) {
+    let event = EscrowEvent { version: EVENT_VERSION, escrow: escrow.clone() };
+    env.events().publish((symbol_short!("escrow"), id, buyer.clone()), event);
+}
+
+pub(crate) fn amended(env: &Env, id: u32, data: &Data, received: i128) {
+    let event = AmendedEvent { version: EVENT_VERSION, data: data.clone(), received };
+    env.events().publish((symbol_short!("amended"), id, data.buyer.clone()), event);
+}
+
+pub(crate) fn bid_info(env: &Env, id: u32, buyer: &Address, bid_info: BidInfo) {
+    let event = BidInfoEvent { version: EVENT_VERSION, bid_info };
+    env.events().publish((symbol_short!("bid_info"), id, buyer.clone()), event);
+}
+
+pub(crate) fn paused(env: &Env, id: u32, buyer: &Address, paused_ledger: u32) {
+    let event = PausedEvent { version: EVENT_VERSION, paused_ledger };
+    env.events().publish((symbol_short!("paused"), id, buyer.clone()), event);
+}
+
+pub(crate) fn resumed(env: &Env, id: u32, buyer: &Address, auction_start_ledger: u32) {
+    let event = ResumedEvent { version: EVENT_VERSION, auction_start_ledger };
+    env.events().publish((symbol_short!("resumed"), id, buyer.clone()), event);
+}
+
+pub(crate) fn operator(env: &Env, id: u32, buyer: &Address, operator: &Address, appointed: bool) {
+    let event = OperatorEvent { version: EVENT_VERSION, operator: operator.clone(), appointed };
+    env.events().publish((symbol_short!("operator"), id, buyer.clone()), event);
+}
+
+pub(crate) fn pending(env: &Env, id: u32, buyer: &Address, pending_sale: &PendingSale, token_received: i128) {
+    let event = PendingEvent { version: EVENT_VERSION, pending_sale: pending_sale.clone(), token_received };
+    env.events().publish((symbol_short!("pending"), id, buyer.clone()), event);
+}
+
+pub(crate) fn disputed(env: &Env, id: u32, buyer: &Address, pending_sale: &PendingSale) {
+    let event = DisputedEvent { version: EVENT_VERSION, pending_sale: pending_sale.clone() };
+    env.events().publish((symbol_short!("disputed"), id, buyer.clone()), event);
+}
+
+pub(crate) fn refunded(env: &Env, id: u32, buyer: &Address, pending_sale: &PendingSale) {
+    let event = RefundedEvent { version: EVENT_VERSION, pending_sale: pending_sale.clone() };
+    env.events().publish((symbol_short!("refunded"), id, buyer.clone()), event);
+}
+
+pub(crate) fn sold(env: &Env, id: u32, buyer: &Address, sale: &SaleRecord, token_received: i128) {
+    let event = SoldEvent { version: EVENT_VERSION, sale: sale.clone(), token_received };
+    env.events().publish((symbol_short!("sold"), id, buyer.clone()), event);
+}
+
+pub(crate) fn cancel(env: &Env, id: u32, buyer: &Address, cancel_ledger: u32) {
+    let event = CancelEvent { version: EVENT_VERSION, cancel_ledger };
+    env.events().publish((symbol_short!("cancel"), id, buyer.clone()), event);
+}
+
+pub(crate) fn aborted(env: &Env, id: u32, buyer: &Address, refund: i128) {
+    let event = AbortedEvent { version: EVENT_VERSION, refund };
+    env.events().publish((symbol_short!("aborted"), id, buyer.clone()), event);
+}
+
+pub(crate) fn closed(env: &Env, id: u32, buyer: &Address, token_amount: i128, counter_token_amount: i128) {
+    let event = ClosedEvent { version: EVENT_VERSION, token_amount, counter_token_amount };
+    env.events().publish((symbol_short!("closed"), id, buyer.clone()), event);
+}
+
+pub(crate) fn reset(env: &Env, id: u32, buyer: &Address) {
+    env.events().publish((symbol_short!("reset"), id, buyer.clone()), ResetEvent { version: EVENT_VERSION });
+}
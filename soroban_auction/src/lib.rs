@@ -1,15 +1,153 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, Env, Address, token, panic_with_error, symbol_short, Symbol};
+use soroban_sdk::{contract, contractimpl, Env, Address, BytesN, IntoVal, token, panic_with_error, Vec};
+
+use auction_pricing::ScheduleParams;
 
 use crate::types::*;
 
-mod types;
+pub mod events;
+mod migration;
+pub mod types;
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+mod validation;
+
+/// Ledgers of TTL kept on top of the remaining duration of an auction
+pub const TTL_BUFFER: u32 = 1000;
+
+/// Approximate number of ledgers closed per day, at ~5 seconds per ledger
+pub const DAY_IN_LEDGERS: u32 = 17280;
+
+/// Minimum TTL of the contract and the current auction after every call, so a finished auction stays live until it is closed
+pub const MIN_TTL: u32 = 7 * DAY_IN_LEDGERS;
+
+/// The TTL is only extended once it has dropped this many ledgers below the required TTL, to avoid paying for a bump on every call
+pub const TTL_THRESHOLD: u32 = DAY_IN_LEDGERS;
+
+/// Maximum number of auctions returned by a single call to get_history
+pub(crate) const MAX_HISTORY_PAGE: u32 = 50;
 
 #[contract]
 pub struct AuctionContract;
 
 #[contractimpl]
 impl AuctionContract {
+    /// Set the admin of the contract. This can only be done once, by the address that deployed the contract
+    /// # Arguments
+    /// - `admin` - Address that deployed the contract, which can rescue tokens sent to it by accident and upgrade it
+    /// - `salt` - Salt the contract was deployed with
+    pub fn initialize(env: Env, admin: Address, salt: BytesN<32>) -> Result<Status, Error> {
+        Self::extending_ttl(&env, || {
+            if env.storage().instance().has(&DataKey::Admin) {
+                return Err(Error::AlreadyIntitialised)
+            }
+            // The address of the contract is derived from its deployer and the salt, so nobody else can claim it in the meantime
+            if env.deployer().with_address(admin.clone(), salt).deployed_address() != env.current_contract_address() {
+                return Err(Error::NotDeployer)
+            }
+            admin.require_auth();
+            env.storage().instance().set(&DataKey::Admin, &admin);
+
+            // A new contract starts at the current storage layout, an older one has to be migrated first
+            if migration::storage_version(&env) == migration::STORAGE_VERSION {
+                env.storage().instance().set(&DataKey::Version, &migration::STORAGE_VERSION);
+            }
+            events::admin_set(&env, &admin);
+
+            Ok(Status::AdminSet)
+        })
+    }
+
+    /// The admin can recover tokens that were sent to the contract by accident.
+    /// Only the balance above what the auction holds in escrow can be rescued
+    /// # Arguments
+    /// - `token` - The token to rescue
+    /// - `to` - Address that will receive the rescued tokens
+    /// - `amount` - Amount to rescue
+    pub fn rescue_tokens(env: Env, token: Address, to: Address, amount: i128) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            if !env.storage().instance().has(&DataKey::Admin) {
+                return Err(Error::NotInitialised)
+            }
+            let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+            admin.require_auth();
+
+            // Add up the escrow the auction holds in this token, if any
+            let mut tracked: i128 = 0;
+            if let Some(id) = Self::auction_id(&env) {
+                let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+                let balances = Self::balances(&env, id);
+                if token == auction_data.token {
+                    tracked += balances.token;
+                }
+                if token == auction_data.counter_token {
+                    tracked += balances.counter_token;
+                }
+            }
+
+            let token_client = token::Client::new(&env, &token);
+            let untracked: i128 = token_client.balance(&env.current_contract_address()) - tracked;
+            if amount < 1 || amount > untracked {
+                return Ok(Status::ExceedsUntracked)
+            }
+            Self::send(&env, &token, &to, amount)?;
+            events::rescued(&env, &admin, &token, &to, amount);
+
+            Ok(Status::Rescued)
+        })
+    }
+
+    /// The admin can upgrade the contract to new code. Call migrate afterwards, so the storage matches the new code.
+    /// The contract can not be upgraded while the current auction holds funds in escrow
+    /// # Arguments
+    /// - `new_wasm_hash` - Hash of the uploaded Wasm of the new contract code
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<Status, Error> {
+        Self::extending_ttl(&env, || {
+            if !env.storage().instance().has(&DataKey::Admin) {
+                return Err(Error::NotInitialised)
+            }
+            let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+            admin.require_auth();
+
+            // New code could move the funds of the buyer and seller, so it has to wait until the auction has paid them out
+            if let Some(id) = Self::auction_id(&env) {
+                if Self::balances(&env, id) != Balances::default() {
+                    return Ok(Status::EscrowHeld)
+                }
+            }
+
+            env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+            events::upgraded(&env, &admin, &new_wasm_hash);
+
+            Ok(Status::Upgraded)
+        })
+    }
+
+    /// The admin migrates the storage of an older version of the contract to the current layout.
+    /// A running auction of an older version continues under the next auction id
+    pub fn migrate(env: Env,) -> Result<Status, Error> {
+        Self::extending_ttl(&env, || {
+            if !env.storage().instance().has(&DataKey::Admin) {
+                return Err(Error::NotInitialised)
+            }
+            let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+            admin.require_auth();
+
+            if migration::storage_version(&env) == migration::STORAGE_VERSION {
+                return Ok(Status::UpToDate)
+            }
+            let from_version: u32 = migration::migrate(&env);
+            events::migrated(&env, &admin, from_version, migration::STORAGE_VERSION);
+
+            Ok(Status::Migrated)
+        })
+    }
+
+    /// Return the version of the storage layout. An older version means the contract has to be migrated
+    pub fn get_storage_version(env: Env, ) -> u32 {
+        migration::storage_version(&env)
+    }
+
     /// Setup a reverse Dutch Auction for an 'auction_token', to be exchanged for 'counter_token'.
     /// The bid will start low, and increase over time untill a sale is made, or a maximum price is reached
     ///
@@ -21,270 +159,980 @@ impl AuctionContract {
     /// - `bid_incr_amount` - Amount the bid will increase each interval
     /// - `bid_incr_times` - Maximum amount of times the bid will increase
     /// - `bid_incr_interval` - Number of ledgers between bid increases
+    /// - `escrow` - Optional arbiter and dispute window. When set, a sale is held in escrow for `dispute_window` ledgers before the seller is paid
+    /// - `start_ledger` - Optional ledger at which the auction starts, to announce it in advance. Defaults to the current ledger
+    /// - `cancel_notice` - Number of ledgers between the buyer closing a running auction and the refund. Sales are still possible during the notice
+    #[allow(clippy::too_many_arguments)]
     pub fn setup_auction(env: Env, buyer: Address,
                          auction_token: Address, 
                          counter_token: Address, 
                          starting_bid: i128, 
                          bid_incr_amount:i128, 
                          bid_incr_times:u32, 
-                         bid_incr_interval: u32
+                         bid_incr_interval: u32,
+                         escrow: Option<EscrowTerms>,
+                         start_ledger: Option<u32>,
+                         cancel_notice: u32,
                         )
                           -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // Require auth for the buyer of the auction, as it will pay for the token it wants to buy
+            buyer.require_auth();
 
-        // Require auth for the buyer of the auction, as it will pay for the token it wants to buy
-        buyer.require_auth();
+            // Panic if the auction already has a State (Running, Fulfilled, Closed or Aborted)
+            if  env.storage().instance().has(&DataKey::AuctionId) {
+                return Ok(Status::AlreadyInitialised);
+            }
 
-        // Panic if the auction already has a State (Running, Fulfilled, Closed or Aborted)
-        if  env.storage().instance().has(&DataKey::State) {
-            return Ok(Status::AlreadyInitialised);
-        }
+            // Validate the parameters, and calculate the maximum price and duration of the auction
+            let current_ledger = env.ledger().sequence();
+            let auction_start_ledger: u32 = start_ledger.unwrap_or(current_ledger);
+            let max_price: i128 = match validation::validate_setup(&env, &buyer, &auction_token, &counter_token,
+                                                            starting_bid, bid_incr_amount, bid_incr_times, bid_incr_interval,
+                                                            &escrow, auction_start_ledger, cancel_notice) {
+                Ok(max_price) => max_price,
+                Err(status) => return Ok(status),
+            };
 
-        // Check if the starting bid and bid-increase amount are positive
-        if starting_bid < 1 || bid_incr_amount < 1 {
-            return Ok(Status::BidMustBePositive)
-        }
-        
-        // Bump the instance to ~ max auction duration + a bit more
-        let required_ttl: u32 = bid_incr_times * bid_incr_interval;
-        env.storage().instance().extend_ttl(required_ttl, required_ttl + 1000);
-
-        // Transfer enough counter-token from the buyer to the contract to pay out the maximum prize.
-        // The auction holds that balance until it is either Fullfilled or Aborted
-        let max_price: i128 = starting_bid + (bid_incr_amount * bid_incr_times as i128);
-        let transfer = token::Client::new(&env, &counter_token)
-                                        .try_transfer(&buyer, &env.current_contract_address(), &max_price);
-        if transfer.is_err() {
-            return Ok(Status::TransferError)
-        }
-        
-        // Set auction details into storage
-        let new_auction_data = Data {
-            buyer: buyer,
-            token: auction_token,
-            counter_token: counter_token,
-            auction_start_ledger: env.ledger().sequence(),
-            bid_start_amount: starting_bid,
-            bid_incr_amount: bid_incr_amount,
-            bid_incr_interval: bid_incr_interval,
-            bid_incr_times: bid_incr_times,
-            bid_max_amount: max_price,
-        };
-        env.storage().instance().set(&DataKey::Data, &new_auction_data);
+            // Every auction gets the next id, under which its entries are kept in persistent storage
+            let id: u32 = env.storage().instance().get::<_, u32>(&DataKey::AuctionCount).unwrap_or(0) + 1;
+            env.storage().instance().set(&DataKey::AuctionCount, &id);
+            env.storage().instance().set(&DataKey::AuctionId, &id);
 
-        // Set the State to Running
-        env.storage().instance().set(&DataKey::State, &State::Running);
+            // Set auction details into storage, before any tokens move
+            let new_auction_data = Data {
+                buyer,
+                token: auction_token,
+                counter_token,
+                auction_start_ledger,
+                bid_start_amount: starting_bid,
+                bid_incr_amount,
+                bid_incr_interval,
+                bid_incr_times,
+                bid_max_amount: max_price,
+                cancel_notice,
+            };
+            env.storage().persistent().set(&AuctionKey::Data(id), &new_auction_data);
 
-        // Emit an event with the auction data, so stakeholders can calculate bid information off-chain
-        env.events().publish((Symbol::new(&env, "auction_data"),), new_auction_data);
+            // Set the State to Running
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Running);
+
+            // If sales are held in escrow, store the arbiter and dispute window
+            if let Some(escrow) = &escrow {
+                env.storage().persistent().set(&AuctionKey::Escrow(id), escrow);
+            }
 
-        // Return the AuctionStarted status
-        Ok(Status::Started)
+            // Transfer enough counter-token from the buyer to the contract to pay out the maximum prize.
+            // The auction holds that balance until it is either Fullfilled or Aborted
+            let received: i128 = Self::receive(&env, &new_auction_data.counter_token, &new_auction_data.buyer, max_price)?;
 
+            // A token that takes a fee on transfer would leave the escrow short of the maximum bid
+            if received < max_price {
+                return Err(Error::TransferAmountMismatch)
+            }
+            Self::set_balances(&env, id, &Balances { counter_token: received, token: 0 });
+
+            // Emit an event with the auction data and escrow, so stakeholders can calculate bid information off-chain
+            events::created(&env, id, &new_auction_data, received);
+
+            // Publish the arbiter and dispute window, if sales are held in escrow
+            if let Some(escrow) = escrow {
+                events::escrow(&env, id, &new_auction_data.buyer, &escrow);
+            }
+
+            // Return the Scheduled status if the auction starts later, otherwise the Started status
+            if auction_start_ledger > current_ledger {
+                return Ok(Status::Scheduled)
+            }
+            Ok(Status::Started)
+        })
     }
 
-    /// Return the current bid that will be paid for the token, upcoming changes and the maximum bid for the token
+    /// Return the current bid that will be paid for the token, upcoming changes and the maximum bid for the token.
+    /// Before the start ledger, the state is NotStarted and the starting bid is reported.
+    /// While the auction is paused, the bid is frozen and the ledgers are reported as if the auction resumed now
     pub fn get_bid_info(env: Env, ) -> Result<BidInfo, Error> {
         
-        // You can only query the price if the auction is Running
-        if  !env.storage().instance().has(&DataKey::State) {
-            panic_with_error!(&env, Error::NotInitialised);
-        }
+        // You can only query the price if the auction is Running, Paused or Cancelling
+        let id: u32 = match Self::auction_id(&env) {
+            Some(id) => id,
+            None => panic_with_error!(&env, Error::NotInitialised),
+        };
         
-        let auction_state: State = env.storage().instance().get(&DataKey::State).unwrap();
+        let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
 
-        if auction_state != State::Running {
+        if auction_state != State::Running && auction_state != State::Paused && auction_state != State::Cancelling {
                 panic_with_error!(&env, Error::NotRunning);
               }
-        // Retrieve the auction data & current ledger
-        let current_ledger = env.ledger().sequence();
-        let auction_data: Data = env.storage().instance().get(&DataKey::Data).unwrap();
-
-        let starting_bid: i128 = auction_data.bid_start_amount;
-        let starting_ledger: u32 = auction_data.auction_start_ledger;
-        let increase_amount: i128 = auction_data.bid_incr_amount;
-        let increase_interval: u32 = auction_data.bid_incr_interval;
-        let increase_times: u32 = auction_data.bid_incr_times;
-
-        // Calculate the bid information
-        let max_bid_ledger = starting_ledger + (increase_interval * increase_times);
-        let max_bid = auction_data.bid_max_amount;
-
-        // Declare these variables, so they can be set inside the if scope, then read outside it
-        let current_bid: i128;
-        let next_bid: i128;
-        let next_bid_ledger: u32;
-        let ledgers_to_next_increase: u32;
-
-        // If the bid has reached its maximum, report that maximum as upcoming bid
-        if current_ledger >= max_bid_ledger {
-            current_bid = max_bid;
-            next_bid = max_bid;
-            next_bid_ledger = 0;
-            ledgers_to_next_increase = 0;
-        } 
-        // If the bid is not yet at it's maximum, report the current and upcoming price/bid info
-        else {
-            let ledgers_passed = current_ledger - starting_ledger;
-            let times_increased: u32 = ledgers_passed / increase_interval;
-
-            current_bid = starting_bid + (increase_amount * times_increased as i128);
-            next_bid = current_bid + increase_amount;
-            next_bid_ledger = starting_ledger + ((times_increased + 1) * increase_interval);
-            ledgers_to_next_increase = next_bid_ledger - current_ledger;
-        }
-
-        let new_bid_info: BidInfo = BidInfo {
-            current_bid,
-            current_ledger,
-            ledgers_to_next_increase,
-            max_bid,
-            max_bid_ledger,
-            next_bid,
-            next_bid_ledger,
-        };
+        // Retrieve the auction data and calculate the bid information at the current ledger
+        let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+        let new_bid_info: BidInfo = Self::bid_info_at(&env, id, auction_state, &auction_data, env.ledger().sequence());
 
         // Publish an event with the bid information, so others can get the information without invoking the contract 
-        env.events().publish((symbol_short!("bid_info"),), new_bid_info);
+        events::bid_info(&env, id, &auction_data.buyer, new_bid_info);
+        Self::extend_ttl(&env);
         Ok(new_bid_info)
 
     }
 
 
-    /// A holder of the token that is being bid for can sell it, and receive the current bid for it
+    /// Return the parameters of the current auction. This does not emit an event
+    pub fn get_auction_data(env: Env, ) -> Result<Data, Error> {
+        let id: u32 = Self::auction_id(&env).ok_or(Error::NotInitialised)?;
+        Ok(env.storage().persistent().get(&AuctionKey::Data(id)).unwrap())
+    }
+
+    /// Return the state of the current auction, reporting NotStarted before its start ledger. This does not emit an event
+    pub fn get_state(env: Env, ) -> Result<State, Error> {
+        let id: u32 = Self::auction_id(&env).ok_or(Error::NotInitialised)?;
+        let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+        let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+        Ok(Self::current_state(auction_state, &auction_data, env.ledger().sequence()))
+    }
+
+    /// Return the bid information as it was, or will be, at the given ledger.
+    /// This works in every state and does not emit an event, so frontends can inspect the schedule cheaply.
+    /// For a Paused auction, the ledgers are reported as if the auction resumed at the given ledger.
+    /// Once the auction is sold, it reports the sale price, as the bid that was reached at the ledger of the sale
     /// # Arguments
-    /// - `seller` - Address that will sell the auction token and receive the payment
-    pub fn sell_token(env: Env, seller: Address ) -> Result<Status, Error> {
+    /// - `ledger` - Ledger sequence to calculate the bid information for
+    pub fn get_bid_info_at(env: Env, ledger: u32) -> Result<BidInfo, Error> {
+        let id: u32 = Self::auction_id(&env).ok_or(Error::NotInitialised)?;
+        let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+        let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+        Ok(Self::bid_info_at(&env, id, auction_state, &auction_data, ledger))
+    }
 
-        // You can only sell the token if the auction is Running
-        if  !env.storage().instance().has(&DataKey::State) {
-            return Ok(Status::NotInitialised);
+    /// Return the parameters, state, current bid information, escrowed balances and the sale price of the current auction.
+    /// This works in every state and does not emit an event
+    pub fn get_auction_summary(env: Env, ) -> Result<AuctionSummary, Error> {
+        let id: u32 = Self::auction_id(&env).ok_or(Error::NotInitialised)?;
+        let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+        let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+        let current_ledger = env.ledger().sequence();
+
+        let sale_price: Option<i128> = Self::final_sale(&env, id, auction_state).map(|(price, _)| price);
+
+        Ok(AuctionSummary {
+            id,
+            bid_info: Self::bid_info_at(&env, id, auction_state, &auction_data, current_ledger),
+            state: Self::current_state(auction_state, &auction_data, current_ledger),
+            balances: Self::balances(&env, id),
+            sale_price,
+            data: auction_data,
+        })
+    }
+
+    /// Return a page of finished auctions that have been reset, oldest first. This does not emit an event
+    /// # Arguments
+    /// - `start` - Sequence number of the first auction to return. Auctions are numbered from 1
+    /// - `limit` - Maximum number of auctions to return, capped at MAX_HISTORY_PAGE
+    pub fn get_history(env: Env, start: u32, limit: u32) -> Vec<AuctionRecord> {
+        let mut history: Vec<AuctionRecord> = Vec::new(&env);
+
+        // Every auction before the current one has been reset, and is part of the history
+        let mut archived: u32 = env.storage().instance().get(&DataKey::AuctionCount).unwrap_or(0);
+        if Self::auction_id(&env).is_some() {
+            // A running auction without a count has no history before it
+            archived = match archived.checked_sub(1) {
+                Some(archived) => archived,
+                None => return history,
+            };
+        }
+
+        let first: u32 = start.max(1);
+        let last: u32 = first.saturating_add(limit.min(MAX_HISTORY_PAGE)).min(archived.saturating_add(1));
+        for id in first..last {
+            // The entries of a reset auction live for the network's maximum TTL. Once archived, they have to be restored
+            // before a page that includes them can be read, as reading an archived entry fails the whole call
+            history.push_back(AuctionRecord {
+                id,
+                data: env.storage().persistent().get(&AuctionKey::Data(id)).unwrap(),
+                state: env.storage().persistent().get(&AuctionKey::State(id)).unwrap(),
+                sale: match env.storage().persistent().get(&AuctionKey::Sale(id)) {
+                    Some(sale) => Sale::Sold(sale),
+                    None => Sale::Unsold,
+                },
+            });
         }
-        let auction_state: State = env.storage().instance().get(&DataKey::State).unwrap();
-        if auction_state != State::Running {
+        history
+    }
+
+    /// A holder of the token that is being bid for can sell it, and receive the current bid for it.
+    /// The sale fails if the bid has dropped below the seller's minimum price, or the sale is submitted too late
+    /// # Arguments
+    /// - `seller` - Address that will sell the auction token and receive the payment
+    /// - `min_price` - Lowest bid the seller accepts, in stroops
+    /// - `valid_until_ledger` - Optional last ledger at which the sale can be made
+    pub fn sell_token(env: Env, seller: Address, min_price: i128, valid_until_ledger: Option<u32>) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            Self::sell(&env, &seller, min_price, valid_until_ledger, |id| {
+                // The seller authorises the sale to this auction by its id, so the authorisation can not be used on a later auction of the contract,
+                // along with its minimum price and the last ledger of the sale
+                seller.require_auth_for_args((id, min_price, valid_until_ledger).into_val(&env));
+                Ok(())
+            })
+        })
+    }
+
+    /// A relayer can settle a sell order that the seller signed off-chain, so the seller does not have to submit (and pay for) the transaction.
+    /// The order is only valid for the auction it names, and is subject to the same minimum price and last ledger as `sell_token`
+    /// # Arguments
+    /// - `order` - The order the seller signed: the auction it is for, the seller, its minimum price and last ledger
+    pub fn settle_signed_order(env: Env, order: SellOrder) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            Self::sell(&env, &order.seller, order.min_price, Some(order.valid_until_ledger), |id| {
+                if order.auction_id != id {
+                    return Err(Error::WrongAuction)
+                }
+                // The seller authorises the order as a whole. The network consumes the nonce of the seller's signature
+                // when the order is settled, so a relayer can not replay it
+                order.seller.require_auth_for_args((order.clone(),).into_val(&env));
+                Ok(())
+            })
+        })
+    }
+
+    /// The buyer, or its operator, can pause a Running auction, for example to wait out a market event.
+    /// While paused the bid does not increase and the token can not be sold, but the escrow stays in place
+    /// # Arguments
+    /// - `caller` - The buyer or the operator of the auction
+    pub fn pause_auction(env: Env, caller: Address) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // Only a Running auction can be paused
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::Running {
                 return Ok(Status::NotRunning);
             }
 
-        // The seller needs to be authorised, since it will transfer the token to the contract
-        seller.require_auth();
+            // Only the buyer of the auction, or its operator, can pause it, authorising it for this auction only
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            if !Self::is_manager(&env, id, &auction_data, &caller) {
+                return Ok(Status::NotManager);
+            }
+            caller.require_auth_for_args((id,).into_val(&env));
 
-        // Retrieve the auction data to read the token and counter_token data
-        let auction_data: Data = env.storage().instance().get(&DataKey::Data).unwrap();
+            // An auction that has not started yet has nothing to pause
+            if Self::current_state(auction_state, &auction_data, env.ledger().sequence()) == State::NotStarted {
+                return Ok(Status::NotStarted);
+            }
 
-        let current_price = Self::get_bid_info(env.clone()).unwrap().current_bid;
+            // Remember when the auction was paused, so the schedule can be shifted when it resumes
+            let current_ledger = env.ledger().sequence();
+            env.storage().persistent().set(&AuctionKey::PausedLedger(id), &current_ledger);
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Paused);
 
-        let auction_token: Address = auction_data.token;
-        let counter_token: Address = auction_data.counter_token;
-        
-        // The amount of the auction token is currently hardcoded to 1 stroop (NFT)
-        // Transfer that 1 stroop from the seller to the contract
-        let transfer = token::Client::new(&env, &auction_token)
-                                                .try_transfer(&seller, &env.current_contract_address(), &1);
-        if transfer.is_err() {
-            return Ok(Status::TransferError)
-        }
-        // Pay the seller the current bid/price
-        let transfer = token::Client::new(&env, &counter_token)
-                                                .try_transfer(&env.current_contract_address(), &seller, &current_price);
-        if transfer.is_err() {
-            return Ok(Status::TransferError)
-        }
+            events::paused(&env, id, &auction_data.buyer, current_ledger);
 
-        // Set the auction State to Fulfilled
-        env.storage().instance().set(&DataKey::State, &State::Fulfilled);
+            Ok(Status::Paused)
+        })
+    }
 
-        // Publish the fact the auction is fulfilled, and the current price
-        env.events().publish((symbol_short!("fulfilled"),), current_price);
+    /// The buyer, or its operator, can resume a Paused auction.
+    /// The bid schedule continues where it was paused, shifted forward by the duration of the pause
+    /// # Arguments
+    /// - `caller` - The buyer or the operator of the auction
+    pub fn resume_auction(env: Env, caller: Address) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::Paused {
+                return Ok(Status::NotPaused);
+            }
 
-        // Return the AuctionFulfilled state to the seller
-        Ok(Status::Fulfilled)
+            // Only the buyer of the auction, or its operator, can resume it, authorising it for this auction only
+            let mut auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            if !Self::is_manager(&env, id, &auction_data, &caller) {
+                return Ok(Status::NotManager);
+            }
+            caller.require_auth_for_args((id,).into_val(&env));
+
+            Self::unpause(&env, id, &mut auction_data);
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Running);
+
+            events::resumed(&env, id, &auction_data.buyer, auction_data.auction_start_ledger);
+
+            Ok(Status::Resumed)
+        })
+    }
+
+    /// The buyer, or its operator, can amend a Running auction, but only in ways that favour sellers.
+    /// The schedule is restarted at the current interval from the new current bid, and the escrow is topped up to the new maximum bid.
+    /// Only the buyer can raise the maximum bid past the escrow it funded, so the operator never moves the buyer's funds
+    /// # Arguments
+    /// - `caller` - The buyer or the operator of the auction
+    /// - `current_bid` - New current bid, at least the bid at this ledger
+    /// - `bid_incr_amount` - New amount the bid will increase each interval, at least the current amount
+    /// - `extra_incr_times` - Number of increases to add to the end of the schedule
+    pub fn amend_auction(env: Env, caller: Address, current_bid: i128, bid_incr_amount: i128, extra_incr_times: u32) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // Only a Running auction can be amended
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::Running {
+                return Ok(Status::NotRunning);
+            }
+
+            // Only the buyer of the auction, or its operator, can amend it. It authorises the amendment of this auction only
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            if !Self::is_manager(&env, id, &auction_data, &caller) {
+                return Ok(Status::NotManager);
+            }
+            caller.require_auth_for_args((id, current_bid, bid_incr_amount, extra_incr_times).into_val(&env));
+
+            // Find how often the bid has increased so far, and the ledger at which the current interval started
+            let current_ledger = env.ledger().sequence();
+            let params: ScheduleParams = Self::schedule_params(&auction_data);
+            let times_increased: u32 = params.times_increased(current_ledger);
+            let intervals_elapsed: u32 = current_ledger.saturating_sub(auction_data.auction_start_ledger) / auction_data.bid_incr_interval;
+            let old_current_bid: i128 = auction_pricing::bid_at(&params, current_ledger).current_bid;
+
+            // Sellers must never be offered less than the schedule they saw, at any future ledger
+            if current_bid < old_current_bid || bid_incr_amount < auction_data.bid_incr_amount {
+                return Ok(Status::AmendmentLowersPrice);
+            }
+
+            // Restart the schedule from the current interval, with the remaining and extra increases
+            let bid_incr_times: u32 = match (auction_data.bid_incr_times - times_increased).checked_add(extra_incr_times) {
+                Some(bid_incr_times) => bid_incr_times,
+                None => return Ok(Status::Overflow),
+            };
+            let max_price: i128 = match bid_incr_amount.checked_mul(bid_incr_times as i128).and_then(|increase| increase.checked_add(current_bid)) {
+                Some(max_price) => max_price,
+                None => return Ok(Status::Overflow),
+            };
+            let new_auction_data = Data {
+                // Once the bid has reached its maximum, the schedule restarts from the interval of the current ledger
+                auction_start_ledger: auction_data.auction_start_ledger + (intervals_elapsed * auction_data.bid_incr_interval),
+                bid_start_amount: current_bid,
+                bid_incr_amount,
+                bid_incr_times,
+                bid_max_amount: max_price,
+                ..auction_data.clone()
+            };
+
+            // The longer schedule must still fit within the network's maximum TTL
+            let max_bid_ledger: Option<u32> = bid_incr_times.checked_mul(new_auction_data.bid_incr_interval)
+                                                .and_then(|ledgers| ledgers.checked_add(new_auction_data.auction_start_ledger));
+            match max_bid_ledger {
+                None => return Ok(Status::Overflow),
+                Some(max_bid_ledger) if max_bid_ledger.saturating_sub(current_ledger).saturating_add(TTL_BUFFER) > env.storage().max_ttl() => {
+                    return Ok(Status::DurationTooLong)
+                }
+                Some(_) => {}
+            }
+
+            // The operator can only amend within the escrow the buyer already funded
+            let top_up: i128 = max_price - auction_data.bid_max_amount;
+            if top_up > 0 && caller != auction_data.buyer {
+                return Ok(Status::ExceedsEscrow);
+            }
+
+            env.storage().persistent().set(&AuctionKey::Data(id), &new_auction_data);
+
+            // Top up the escrow, so the contract can still pay out the new maximum bid
+            let mut received: i128 = 0;
+            if top_up > 0 {
+                received = Self::receive(&env, &auction_data.counter_token, &auction_data.buyer, top_up)?;
+                if received < top_up {
+                    return Err(Error::TransferAmountMismatch)
+                }
+                let mut balances = Self::balances(&env, id);
+                balances.counter_token += received;
+                Self::set_balances(&env, id, &balances);
+            }
+
+            // Emit the amended auction data and top-up, so stakeholders can recalculate bid information off-chain
+            events::amended(&env, id, &new_auction_data, received);
+
+            Ok(Status::Amended)
+        })
+    }
+
+    /// The buyer can appoint an operator, to pause, resume and amend the auction on its behalf.
+    /// The operator can not close or reset the auction, dispute a sale, nor top up the escrow, so it never moves the buyer's funds
+    /// # Arguments
+    /// - `operator` - The new operator, or None to remove the current one
+    pub fn set_operator(env: Env, operator: Option<Address>) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state == State::Closed || auction_state == State::Aborted {
+                return Ok(Status::AlreadyClosed);
+            }
+
+            // Only the buyer of the auction can appoint its operator, authorising it for this auction only
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            auction_data.buyer.require_auth_for_args((id, operator.clone()).into_val(&env));
+
+            // Publish the removed operator as well, so stakeholders know who no longer manages the auction
+            let previous: Option<Address> = env.storage().persistent().get(&AuctionKey::Operator(id));
+            if let Some(previous) = previous.filter(|previous| operator.as_ref() != Some(previous)) {
+                env.storage().persistent().remove(&AuctionKey::Operator(id));
+                events::operator(&env, id, &auction_data.buyer, &previous, false);
+            }
+            if let Some(operator) = &operator {
+                env.storage().persistent().set(&AuctionKey::Operator(id), operator);
+                events::operator(&env, id, &auction_data.buyer, operator, true);
+            }
+
+            Ok(Status::OperatorSet)
+        })
+    }
+
+    /// During the dispute window of an escrowed sale, the buyer can dispute it.
+    /// The sale is then held until the arbiter resolves the dispute
+    pub fn raise_dispute(env: Env,) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // A dispute can only be raised while a sale is pending release
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::PendingRelease {
+                return Ok(Status::NotPendingRelease);
+            }
+
+            // Only the buyer of the auction can dispute the sale, authorising it for this auction only
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            auction_data.buyer.require_auth_for_args((id,).into_val(&env));
+
+            let pending_sale: PendingSale = env.storage().persistent().get(&AuctionKey::PendingSale(id)).unwrap();
+            if env.ledger().sequence() >= pending_sale.release_ledger {
+                return Ok(Status::DisputeWindowClosed);
+            }
+
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Disputed);
+            events::disputed(&env, id, &auction_data.buyer, &pending_sale);
+
+            Ok(Status::DisputeRaised)
+        })
+    }
+
+    /// The arbiter of an escrowed auction resolves a dispute.
+    /// Either the payment is released to the seller, or the token is returned to the seller and the buyer is refunded
+    /// # Arguments
+    /// - `release` - True to pay the seller, false to refund the buyer
+    pub fn resolve_dispute(env: Env, release: bool) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // Only a Disputed sale can be resolved
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::Disputed {
+                return Ok(Status::NotDisputed);
+            }
+
+            // Only the arbiter set up in the auction can resolve the dispute, authorising the outcome for this auction only
+            let escrow: EscrowTerms = env.storage().persistent().get(&AuctionKey::Escrow(id)).unwrap();
+            escrow.arbiter.require_auth_for_args((id, release).into_val(&env));
+
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            let pending_sale: PendingSale = env.storage().persistent().get(&AuctionKey::PendingSale(id)).unwrap();
+
+            if release {
+                return Self::release_sale(&env, id, &auction_data, &pending_sale);
+            }
+
+            // The auction ended without a sale, same as an abort
+            let balances = Self::balances(&env, id);
+            Self::set_balances(&env, id, &Balances::default());
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Aborted);
+
+            // Return the token to the seller, and refund the escrowed funds to the buyer
+            Self::send(&env, &auction_data.token, &pending_sale.seller, balances.token)?;
+            Self::send(&env, &auction_data.counter_token, &auction_data.buyer, balances.counter_token)?;
+
+            events::refunded(&env, id, &auction_data.buyer, &pending_sale);
+
+            Ok(Status::Refunded)
+        })
+    }
+
+    /// Anyone can finalize an escrowed sale once the dispute window has passed without a dispute.
+    /// This pays the seller, after which the buyer can close the auction as usual
+    pub fn finalize(env: Env,) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state != State::PendingRelease {
+                return Ok(Status::NotPendingRelease);
+            }
+
+            let pending_sale: PendingSale = env.storage().persistent().get(&AuctionKey::PendingSale(id)).unwrap();
+            if env.ledger().sequence() < pending_sale.release_ledger {
+                return Ok(Status::DisputeWindowOpen);
+            }
+
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            Self::release_sale(&env, id, &auction_data, &pending_sale)
+        })
     }
 
     /// The auction buyer/organiser can close the auction.
     /// If this is done while the auction is still running, they receive back the funds they deposited.
+    /// With a cancellation notice, the first call starts the notice and the funds are returned by a call after the notice has passed.
     /// If it is done after the auction was fulfilled, they receive the token in question, and any remaining funds
     pub fn close_auction(env: Env,) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            // Only allow termination if the auction is either running or finished
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Ok(Status::NotInitialised),
+            };
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state == State::Closed || auction_state == State::Aborted{
+                    return Ok(Status::AlreadyClosed);
+                }
+
+            // Load the auction data
+            let mut auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+
+            // Only the buyer of the auction can terminate it, authorising it for this auction only
+            auction_data.buyer.require_auth_for_args((id,).into_val(&env));
+
+            // A started auction with a cancellation notice keeps running for the notice period, so sellers can not be front-run
+            let live = auction_state == State::Running || auction_state == State::Paused;
+            if live && auction_data.cancel_notice > 0 && Self::current_state(auction_state, &auction_data, env.ledger().sequence()) != State::NotStarted {
+                // The schedule runs on during the notice, so publish where a paused one continues from
+                if auction_state == State::Paused {
+                    Self::unpause(&env, id, &mut auction_data);
+                    events::resumed(&env, id, &auction_data.buyer, auction_data.auction_start_ledger);
+                }
+                let cancel_ledger: u32 = env.ledger().sequence() + auction_data.cancel_notice;
+                env.storage().persistent().set(&AuctionKey::CancelLedger(id), &cancel_ledger);
+                env.storage().persistent().set(&AuctionKey::State(id), &State::Cancelling);
+
+                // Publish the ledger from which the refund can be executed
+                events::cancel(&env, id, &auction_data.buyer, cancel_ledger);
+
+                return Ok(Status::Cancelling)
+            }
+            if auction_state == State::Cancelling {
+                let cancel_ledger: u32 = env.storage().persistent().get(&AuctionKey::CancelLedger(id)).unwrap();
+                if env.ledger().sequence() < cancel_ledger {
+                    return Ok(Status::NoticePeriod)
+                }
+            }
+
+            let buyer: Address = auction_data.buyer;
+            let counter_token: Address = auction_data.counter_token;
+
+            // Only pay out what this auction holds, not other tokens sent to the contract
+            let balances = Self::balances(&env, id);
+            let counter_token_balance = balances.counter_token;
+
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
 
-        // Only allow termination if the auction is either running or finished
-        if  !env.storage().instance().has(&DataKey::State) {
-            return Ok(Status::NotInitialised);
+
+            if live || auction_state == State::Cancelling {
+                // Auction is running, paused or its notice has passed, so set status to Aborted and pay the counter_token back to the buyer
+                Self::set_balances(&env, id, &Balances::default());
+                env.storage().persistent().set(&AuctionKey::State(id), &State::Aborted);
+                Self::send(&env, &counter_token, &buyer, counter_token_balance)?;
+                events::aborted(&env, id, &buyer, counter_token_balance);
+                return Ok(Status::Aborted)
+
+            }
+            if auction_state == State::Fulfilled {
+                // Auction is Fulfilled, so set the State to Closed before paying out
+                Self::set_balances(&env, id, &Balances::default());
+                env.storage().persistent().set(&AuctionKey::State(id), &State::Closed);
+
+                // Pay out the aquired token
+                let auction_token: Address = auction_data.token;
+                Self::send(&env, &auction_token, &buyer, balances.token)?;
+
+                // If any funds remain, return them to the buyer
+                if counter_token_balance > 0 {
+                    Self::send(&env, &counter_token, &buyer, counter_token_balance)?;
+                }
+
+                events::closed(&env, id, &buyer, balances.token, counter_token_balance);
+
+                // Return the AuctionClosed status
+                return Ok(Status::Closed)
+
+            }
+            // A sale that is pending release or disputed can not be closed until it is resolved
+            Err(Error::NotRunning)
+        })
+    }
+
+    /// Anyone can pay to extend the TTL of the contract and the current auction.
+    /// This keeps an auction live when nobody has called it for a long time, for example while it is paused or waiting to be closed
+    pub fn bump(env: Env,) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            Ok(Status::Bumped)
+        })
+    }
+
+    /// For demonstration purposes, the buyer can reset the auction contract.
+    /// This allows for re-use of the contract after it has been Closed or Aborted.
+    /// The data, final state and sale of the auction are kept in the history, see get_history
+    pub fn reset_auction(env: Env,) -> Result<Status, Error> {
+        Self::migrated(&env, || {
+            let id: u32 = match Self::auction_id(&env) {
+                Some(id) => id,
+                None => return Err(Error::NotInitialised),
+            };
+            // Only the buyer of the auction can reset it, authorising it for this auction only
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            auction_data.buyer.require_auth_for_args((id,).into_val(&env));
+
+            let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+            if auction_state == State::Closed || auction_state == State::Aborted {
+                // Archive the auction by keeping its Data, State and Sale entries, and remove the rest
+                env.storage().persistent().remove(&AuctionKey::Escrow(id));
+                env.storage().persistent().remove(&AuctionKey::PendingSale(id));
+                env.storage().persistent().remove(&AuctionKey::PausedLedger(id));
+                env.storage().persistent().remove(&AuctionKey::CancelLedger(id));
+                env.storage().persistent().remove(&AuctionKey::Balances(id));
+                env.storage().persistent().remove(&AuctionKey::Operator(id));
+
+                // The entries that are kept are no longer extended by later calls, so keep them for as long as the network allows
+                let history_ttl: u32 = env.storage().max_ttl();
+                for key in [AuctionKey::Data(id), AuctionKey::State(id), AuctionKey::Sale(id)] {
+                    if env.storage().persistent().has(&key) {
+                        env.storage().persistent().extend_ttl(&key, history_ttl, history_ttl);
+                    }
+                }
+                env.storage().instance().remove(&DataKey::AuctionId);
+                events::reset(&env, id, &auction_data.buyer);
+
+                return Ok(Status::Reset);
+            }
+            Err(Error::NotYetClosed)      
+        })
+    }
+}
+
+impl AuctionContract {
+    /// The amounts of the token and counter_token the auction holds in escrow
+    fn balances(env: &Env, id: u32) -> Balances {
+        env.storage().persistent().get(&AuctionKey::Balances(id)).unwrap_or_default()
+    }
+
+    fn set_balances(env: &Env, id: u32, balances: &Balances) {
+        env.storage().persistent().set(&AuctionKey::Balances(id), balances);
+    }
+
+    /// Transfer `amount` of `token` from `from` to the contract, and return the amount the contract actually received.
+    /// This is measured as the change in the contract's balance, as fee-on-transfer or rebasing tokens can deliver a different amount
+    fn receive(env: &Env, token: &Address, from: &Address, amount: i128) -> Result<i128, Error> {
+        let token_client = token::Client::new(env, token);
+        let balance_before: i128 = token_client.balance(&env.current_contract_address());
+        if token_client.try_transfer(from, &env.current_contract_address(), &amount).is_err() {
+            return Err(Error::TransferFailed)
         }
-        let auction_state: State = env.storage().instance().get(&DataKey::State).unwrap();
-        if auction_state == State::Closed || auction_state == State::Aborted{
-                return Ok(Status::AlreadyClosed);
+        Ok(token_client.balance(&env.current_contract_address()) - balance_before)
+    }
+
+    /// Transfer `amount` of `token` from the contract to `to`.
+    /// A failed transfer returns an error, so all state changes made before it are rolled back
+    fn send(env: &Env, token: &Address, to: &Address, amount: i128) -> Result<(), Error> {
+        let transfer = token::Client::new(env, token).try_transfer(&env.current_contract_address(), to, &amount);
+        if transfer.is_err() {
+            return Err(Error::TransferFailed)
+        }
+        Ok(())
+    }
+
+    /// Run an entrypoint of the current storage layout. Until the admin has migrated the storage of an older version,
+    /// only initialize, upgrade and migrate can be called
+    fn migrated<F: FnOnce() -> Result<Status, Error>>(env: &Env, entrypoint: F) -> Result<Status, Error> {
+        if migration::storage_version(env) != migration::STORAGE_VERSION {
+            return Err(Error::NotMigrated)
+        }
+        Self::extending_ttl(env, entrypoint)
+    }
+
+    /// Run an entrypoint, and extend the TTL afterwards.
+    /// There is no lock against reentry, as the host already fails a token contract that calls back into the contract
+    fn extending_ttl<F: FnOnce() -> Result<Status, Error>>(env: &Env, entrypoint: F) -> Result<Status, Error> {
+        let result = entrypoint();
+
+        // Any successful call keeps the contract and the current auction live
+        if result.is_ok() {
+            Self::extend_ttl(env);
+        }
+        result
+    }
+
+    /// A Running auction is reported as NotStarted until its start ledger is reached
+    fn current_state(auction_state: State, auction_data: &Data, ledger: u32) -> State {
+        if auction_state == State::Running && ledger < auction_data.auction_start_ledger {
+            return State::NotStarted
+        }
+        auction_state
+    }
+
+    /// Calculate the bid information of an auction at the given ledger, without touching storage.
+    /// A Paused auction has its schedule shifted forward by the ledgers it has been paused at that ledger
+    fn bid_info_at(env: &Env, id: u32, auction_state: State, auction_data: &Data, ledger: u32) -> BidInfo {
+        let params: ScheduleParams = Self::schedule_params(auction_data);
+
+        // Once sold, the bid stays at the sale price, reached at the ledger of the sale
+        if let Some((price, sale_ledger)) = Self::final_sale(env, id, auction_state) {
+            return BidInfo {
+                current_bid: price,
+                current_ledger: ledger,
+                ledgers_to_next_increase: 0,
+                max_bid: price,
+                max_bid_ledger: sale_ledger,
+                next_bid: price,
+                next_bid_ledger: 0,
+                start_ledger: params.start_ledger,
+                state: Self::current_state(auction_state, auction_data, ledger),
             }
-        
-        // Load the auction data
-        let auction_data: Data = env.storage().instance().get(&DataKey::Data).unwrap();
-        let buyer: Address = auction_data.buyer;
-        let counter_token: Address = auction_data.counter_token;
+        }
 
-        // Only the buyer of the auction can terminate it
-        buyer.require_auth();
+        // While paused, the bid is frozen at the paused ledger, and the schedule shifts forward by the ledgers spent paused so far.
+        // The shifted ledgers saturate, so even the last ledger can be queried
+        let mut at_ledger: u32 = ledger;
+        if auction_state == State::Paused {
+            let paused_ledger: u32 = env.storage().persistent().get(&AuctionKey::PausedLedger(id)).unwrap();
+            at_ledger = ledger.min(paused_ledger);
+        }
+        let shift: u32 = ledger - at_ledger;
+        let bid = auction_pricing::bid_at(&params, at_ledger);
 
-        let counter_token_balance = token::Client::new(&env, &counter_token).balance(&env.current_contract_address());
-        
-        let auction_state: State = env.storage().instance().get(&DataKey::State).unwrap();
+        BidInfo {
+            current_bid: bid.current_bid,
+            current_ledger: ledger,
+            ledgers_to_next_increase: bid.ledgers_to_next_increase,
+            max_bid: bid.max_bid,
+            max_bid_ledger: bid.max_bid_ledger.saturating_add(shift),
+            next_bid: bid.next_bid,
+            next_bid_ledger: if bid.next_bid_ledger == 0 { 0 } else { bid.next_bid_ledger.saturating_add(shift) },
+            start_ledger: params.start_ledger.saturating_add(shift),
+            state: Self::current_state(auction_state, auction_data, ledger),
+        }
+    }
 
-        
-        if auction_state == State::Running {
-            // Auction is running, so pay the counter_token back to the buyer and set status to Aborted
-            let transfer = token::Client::new(&env, &counter_token)
-                                            .try_transfer(&env.current_contract_address(), &buyer, &counter_token_balance);
-            if transfer.is_err() {
-                return Ok(Status::TransferError)
+    /// The price and ledger of the sale of the current auction, if it was sold.
+    /// A sale held in escrow already has its price, even though it has not been paid out yet
+    fn final_sale(env: &Env, id: u32, auction_state: State) -> Option<(i128, u32)> {
+        match auction_state {
+            State::PendingRelease | State::Disputed => {
+                let pending_sale: PendingSale = env.storage().persistent().get(&AuctionKey::PendingSale(id)).unwrap();
+                let escrow: EscrowTerms = env.storage().persistent().get(&AuctionKey::Escrow(id)).unwrap();
+                Some((pending_sale.price, pending_sale.release_ledger - escrow.dispute_window))
+            }
+            State::Fulfilled | State::Closed => {
+                env.storage().persistent().get::<_, SaleRecord>(&AuctionKey::Sale(id)).map(|sale| (sale.price, sale.ledger))
             }
-            env.storage().instance().set(&DataKey::State, &State::Aborted);
-            return Ok(Status::Aborted)
+            _ => None,
+        }
+    }
 
+    /// The parameters of the bid schedule of an auction
+    fn schedule_params(auction_data: &Data) -> ScheduleParams {
+        ScheduleParams {
+            start_ledger: auction_data.auction_start_ledger,
+            start_bid: auction_data.bid_start_amount,
+            incr_amount: auction_data.bid_incr_amount,
+            incr_interval: auction_data.bid_incr_interval,
+            incr_times: auction_data.bid_incr_times,
         }
-        if auction_state == State::Fulfilled {
-            // Auction is Fulfilled, so pay out the aquired token
-            let auction_token: Address = auction_data.token;
-            let auction_token_balance: i128 = token::Client::new(&env, &auction_token).balance(&env.current_contract_address());
-
-            let transfer = token::Client::new(&env, &auction_token)
-                                            .try_transfer(&env.current_contract_address(), &buyer, &auction_token_balance);
-            if transfer.is_err() {
-                return Ok(Status::TransferError)
-            }
-
-            // If any funds remain, return them to the buyer
-            if counter_token_balance > 0 {
-                let transfer = token::Client::new(&env, &counter_token)
-                                            .try_transfer(&env.current_contract_address(), &buyer, &counter_token_balance);
-                if transfer.is_err() {
-                    return Ok(Status::TransferError)
+    }
+
+    /// Shift the start of a Paused auction forward by the ledgers it was paused, so the schedule continues where it was paused
+    fn unpause(env: &Env, id: u32, auction_data: &mut Data) {
+        let paused_ledger: u32 = env.storage().persistent().get(&AuctionKey::PausedLedger(id)).unwrap();
+        auction_data.auction_start_ledger += env.ledger().sequence() - paused_ledger;
+        env.storage().persistent().set(&AuctionKey::Data(id), auction_data);
+        env.storage().persistent().remove(&AuctionKey::PausedLedger(id));
+    }
+
+    /// Extend the TTL of the contract instance and the entries of the current auction.
+    /// They are kept live until the schedule has ended, a pending sale can be released and a cancellation can be refunded,
+    /// plus a bit more, and at least MIN_TTL, so an auction that still holds funds after that does not get archived
+    fn extend_ttl(env: &Env) {
+        let mut required_ttl: u32 = MIN_TTL.min(env.storage().max_ttl());
+
+        if let Some(id) = Self::auction_id(env) {
+            let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
+            let max_bid_ledger: u32 = auction_data.auction_start_ledger
+                                        .saturating_add(auction_data.bid_incr_interval.saturating_mul(auction_data.bid_incr_times));
+            let release_ledger: u32 = env.storage().persistent().get::<_, PendingSale>(&AuctionKey::PendingSale(id))
+                                        .map_or(0, |pending_sale| pending_sale.release_ledger);
+            let cancel_ledger: u32 = env.storage().persistent().get(&AuctionKey::CancelLedger(id)).unwrap_or(0);
+            let end_ledger: u32 = max_bid_ledger.max(release_ledger).max(cancel_ledger);
+            let remaining_ttl: u32 = end_ledger.saturating_sub(env.ledger().sequence()).saturating_add(TTL_BUFFER);
+            required_ttl = required_ttl.max(remaining_ttl).min(env.storage().max_ttl());
+
+            let keys = [
+                AuctionKey::Data(id),
+                AuctionKey::State(id),
+                AuctionKey::Balances(id),
+                AuctionKey::Escrow(id),
+                AuctionKey::PendingSale(id),
+                AuctionKey::PausedLedger(id),
+                AuctionKey::CancelLedger(id),
+                AuctionKey::Sale(id),
+                AuctionKey::Operator(id),
+            ];
+            for key in keys {
+                if env.storage().persistent().has(&key) {
+                    env.storage().persistent().extend_ttl(&key, Self::ttl_threshold(required_ttl), required_ttl);
                 }
             }
+        }
+
+        env.storage().instance().extend_ttl(Self::ttl_threshold(required_ttl), required_ttl);
+    }
+
+    /// The TTL below which an entry is extended to `required_ttl`. On a network whose maximum TTL is below TTL_THRESHOLD,
+    /// entries are extended on every call rather than never
+    fn ttl_threshold(required_ttl: u32) -> u32 {
+        required_ttl.checked_sub(TTL_THRESHOLD).unwrap_or(required_ttl)
+    }
+
+    /// The id of the current auction, if there is one
+    fn auction_id(env: &Env) -> Option<u32> {
+        env.storage().instance().get(&DataKey::AuctionId)
+    }
+
+    /// Sell the token to the buyer at the current bid, after the seller is authorised by `authorise`
+    fn sell<F: FnOnce(u32) -> Result<(), Error>>(env: &Env, seller: &Address, min_price: i128, valid_until_ledger: Option<u32>, authorise: F) -> Result<Status, Error> {
+        // You can only sell the token if the auction is Running
+        let id: u32 = match Self::auction_id(env) {
+            Some(id) => id,
+            None => return Ok(Status::NotInitialised),
+        };
+        let auction_state: State = env.storage().persistent().get(&AuctionKey::State(id)).unwrap();
+        if auction_state == State::Paused {
+                return Ok(Status::Paused);
+            }
+        // During the cancellation notice, sales still honour the current bid
+        if auction_state != State::Running && auction_state != State::Cancelling {
+                return Ok(Status::NotRunning);
+            }
 
-            // Set the State to Closed
-            env.storage().instance().set(&DataKey::State, &State::Closed);
+        // The seller needs to be authorised, since it will transfer the token to the contract
+        authorise(id)?;
 
-            // Return the AuctionClosed status
-            return Ok(Status::Closed)
+        // Retrieve the auction data to read the token and counter_token data
+        let auction_data: Data = env.storage().persistent().get(&AuctionKey::Data(id)).unwrap();
 
+        // The token can not be sold before the auction has started
+        if Self::current_state(auction_state, &auction_data, env.ledger().sequence()) == State::NotStarted {
+            return Ok(Status::NotStarted);
         }
-        Err(Error::NotRunning)
 
-    }
+        // The seller never gets less than it agreed to, nor sells after the ledger it agreed to
+        if valid_until_ledger.is_some_and(|valid_until_ledger| env.ledger().sequence() > valid_until_ledger) {
+            return Err(Error::SaleExpired)
+        }
+        let current_price = Self::bid_info_at(env, id, auction_state, &auction_data, env.ledger().sequence()).current_bid;
+        if current_price < min_price {
+            return Err(Error::PriceBelowMinimum)
+        }
 
-    /// For demonstration purposes, the buyer can reset the auction contract.
-    /// This allows for re-use of the contract after it has been Closed or Aborted
-    pub fn reset_auction(env: Env,) -> Result<Status, Error> {
-        if  !env.storage().instance().has(&DataKey::State) {
-            return Err(Error::NotInitialised)
+        let auction_token: Address = auction_data.token;
+        let counter_token: Address = auction_data.counter_token;
+        let mut balances = Self::balances(env, id);
+
+        // Record the sale before any tokens move.
+        // If the auction uses escrow, hold the payment until the dispute window has passed
+        let escrow: Option<EscrowTerms> = env.storage().persistent().get(&AuctionKey::Escrow(id));
+        let pending_sale: Option<PendingSale> = escrow.map(|escrow| PendingSale {
+            seller: seller.clone(),
+            price: current_price,
+            release_ledger: env.ledger().sequence() + escrow.dispute_window,
+        });
+        let sale: Option<SaleRecord> = if let Some(pending_sale) = &pending_sale {
+            env.storage().persistent().set(&AuctionKey::PendingSale(id), pending_sale);
+            env.storage().persistent().set(&AuctionKey::State(id), &State::PendingRelease);
+            None
+        } else {
+            // Set the auction State to Fulfilled
+            balances.counter_token -= current_price;
+            env.storage().persistent().set(&AuctionKey::State(id), &State::Fulfilled);
+            Some(Self::record_sale(env, id, seller, current_price))
+        };
+
+        // The amount of the auction token is currently hardcoded to 1 stroop (NFT)
+        // Transfer that 1 stroop from the seller to the contract
+        let received: i128 = Self::receive(env, &auction_token, seller, 1)?;
+
+        // The buyer must actually receive the token it pays for
+        if received < 1 {
+            return Err(Error::TransferAmountMismatch)
         }
-        let auction_data: Data = env.storage().instance().get(&DataKey::Data).unwrap();
-        auction_data.buyer.require_auth();
-
-        let auction_state: State = env.storage().instance().get(&DataKey::State).unwrap();
-        if auction_state == State::Closed || auction_state == State::Aborted {
-            env.storage().instance().remove(&DataKey::State);
-            env.storage().instance().remove(&DataKey::Data);
-            
-            return Ok(Status::Reset);
+        balances.token += received;
+        Self::set_balances(env, id, &balances);
+
+        if let Some(pending_sale) = pending_sale {
+            // Publish the pending sale, so the buyer knows when the dispute window closes
+            events::pending(env, id, &auction_data.buyer, &pending_sale, received);
+
+            return Ok(Status::PendingRelease)
+        }
+
+        // Pay the seller the current bid/price
+        Self::send(env, &counter_token, seller, current_price)?;
+
+        // Publish the fact the auction is fulfilled, and the sale
+        if let Some(sale) = sale {
+            events::sold(env, id, &auction_data.buyer, &sale, received);
         }
-        Err(Error::NotYetClosed)      
+
+        // Return the AuctionFulfilled state to the seller
+        Ok(Status::Fulfilled)
+    }
+
+    /// Whether `caller` can manage the auction: its buyer, or the operator the buyer appointed
+    fn is_manager(env: &Env, id: u32, auction_data: &Data, caller: &Address) -> bool {
+        *caller == auction_data.buyer || env.storage().persistent().get(&AuctionKey::Operator(id)).as_ref() == Some(caller)
+    }
+
+    /// Store the result of a sale, so the realised price stays on-chain after the auction is reset
+    fn record_sale(env: &Env, id: u32, seller: &Address, price: i128) -> SaleRecord {
+        let sale = SaleRecord {
+            seller: seller.clone(),
+            price,
+            ledger: env.ledger().sequence(),
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&AuctionKey::Sale(id), &sale);
+        sale
+    }
+
+    /// Pay the seller of an escrowed sale and mark the auction as Fulfilled
+    fn release_sale(env: &Env, id: u32, auction_data: &Data, pending_sale: &PendingSale) -> Result<Status, Error> {
+        let mut balances = Self::balances(env, id);
+        balances.counter_token -= pending_sale.price;
+        Self::set_balances(env, id, &balances);
+        env.storage().persistent().set(&AuctionKey::State(id), &State::Fulfilled);
+        let sale = Self::record_sale(env, id, &pending_sale.seller, pending_sale.price);
+
+        Self::send(env, &auction_data.counter_token, &pending_sale.seller, pending_sale.price)?;
+
+        // The token was already received with the pending sale
+        events::sold(env, id, &auction_data.buyer, &sale, 0);
+
+        Ok(Status::Released)
     }
 }
 
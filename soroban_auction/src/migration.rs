@@ -0,0 +1,143 @@
+//! Storage layouts of earlier versions of the contract, and their migration to the current layout.
+//!
+//! - Version 0 (`lib.bak`) kept a single auction in instance storage, as `AuctionData` with a `host`.
+//! - Version 1 kept a single auction in instance storage, as `Data` with a `buyer`.
+//! - Version 2 keeps every auction under its own id in persistent storage, see `AuctionKey`.
+use soroban_sdk::{contracttype, token, Address, Env};
+
+use crate::types::{AuctionKey, Balances, Data, DataKey, State};
+
+/// Version of the storage layout written by this contract
+pub(crate) const STORAGE_VERSION: u32 = 2;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum LegacyDataKey {
+    State,
+    AuctionData,
+    Data,
+}
+
+/// The auction data of version 0
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct AuctionDataV0 {
+    pub host: Address,
+    pub token: Address,
+    pub counter_token: Address,
+    pub auction_start_ledger: u32,
+    pub bid_start_amount: i128,
+    pub bid_incr_amount: i128,
+    pub bid_incr_interval: u32,
+    pub bid_incr_times: u32,
+    pub bid_max_amount: i128,
+}
+
+/// The auction data of version 1
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct DataV1 {
+    pub buyer: Address,
+    pub token: Address,
+    pub counter_token: Address,
+    pub auction_start_ledger: u32,
+    pub bid_start_amount: i128,
+    pub bid_incr_amount: i128,
+    pub bid_incr_interval: u32,
+    pub bid_incr_times: u32,
+    pub bid_max_amount: i128,
+}
+
+impl From<AuctionDataV0> for DataV1 {
+    fn from(data: AuctionDataV0) -> Self {
+        DataV1 {
+            buyer: data.host,
+            token: data.token,
+            counter_token: data.counter_token,
+            auction_start_ledger: data.auction_start_ledger,
+            bid_start_amount: data.bid_start_amount,
+            bid_incr_amount: data.bid_incr_amount,
+            bid_incr_interval: data.bid_incr_interval,
+            bid_incr_times: data.bid_incr_times,
+            bid_max_amount: data.bid_max_amount,
+        }
+    }
+}
+
+impl From<DataV1> for Data {
+    fn from(data: DataV1) -> Self {
+        Data {
+            buyer: data.buyer,
+            token: data.token,
+            counter_token: data.counter_token,
+            auction_start_ledger: data.auction_start_ledger,
+            bid_start_amount: data.bid_start_amount,
+            bid_incr_amount: data.bid_incr_amount,
+            bid_incr_interval: data.bid_incr_interval,
+            bid_incr_times: data.bid_incr_times,
+            bid_max_amount: data.bid_max_amount,
+            cancel_notice: 0,
+        }
+    }
+}
+
+/// The version of the storage layout. Contracts that have not been migrated yet have no stored version,
+/// so it is derived from the keys that are present
+pub(crate) fn storage_version(env: &Env) -> u32 {
+    if let Some(version) = env.storage().instance().get(&DataKey::Version) {
+        return version
+    }
+    if env.storage().instance().has(&LegacyDataKey::AuctionData) {
+        return 0
+    }
+    if env.storage().instance().has(&LegacyDataKey::Data) {
+        return 1
+    }
+    STORAGE_VERSION
+}
+
+/// Move the auction of an older layout, if any, to the current layout and store the current version.
+/// Returns the version that was migrated from
+pub(crate) fn migrate(env: &Env) -> u32 {
+    let version = storage_version(env);
+
+    let legacy_data: Option<DataV1> = match version {
+        0 => env.storage().instance().get::<_, AuctionDataV0>(&LegacyDataKey::AuctionData).map(DataV1::from),
+        1 => env.storage().instance().get(&LegacyDataKey::Data),
+        _ => None,
+    };
+
+    if let Some(legacy_data) = legacy_data {
+        let auction_data: Data = legacy_data.into();
+        let auction_state: State = env.storage().instance().get(&LegacyDataKey::State).unwrap();
+
+        // The older layouts did not track their escrow. A Running auction holds the maximum bid, and a Fulfilled one the token
+        // and what is left of the maximum bid, which is at most the maximum bid less the starting bid, as the price is not stored.
+        // Anything the contract holds above that was sent by accident, and is left untracked so the admin can rescue it
+        let mut balances = Balances::default();
+        if auction_state == State::Running || auction_state == State::Fulfilled {
+            let (token_escrow, counter_token_escrow): (i128, i128) = match auction_state {
+                State::Running => (0, auction_data.bid_max_amount),
+                _ => (1, auction_data.bid_max_amount - auction_data.bid_start_amount),
+            };
+            let contract = env.current_contract_address();
+            balances.token = token::Client::new(env, &auction_data.token).balance(&contract).min(token_escrow);
+            balances.counter_token = token::Client::new(env, &auction_data.counter_token).balance(&contract).min(counter_token_escrow);
+        }
+
+        let id: u32 = env.storage().instance().get::<_, u32>(&DataKey::AuctionCount).unwrap_or(0) + 1;
+        env.storage().instance().set(&DataKey::AuctionCount, &id);
+        env.storage().instance().set(&DataKey::AuctionId, &id);
+        env.storage().persistent().set(&AuctionKey::Data(id), &auction_data);
+        env.storage().persistent().set(&AuctionKey::State(id), &auction_state);
+        env.storage().persistent().set(&AuctionKey::Balances(id), &balances);
+    }
+
+    env.storage().instance().remove(&LegacyDataKey::State);
+    env.storage().instance().remove(&LegacyDataKey::AuctionData);
+    env.storage().instance().remove(&LegacyDataKey::Data);
+    env.storage().instance().set(&DataKey::Version, &STORAGE_VERSION);
+
+    version
+}
@@ -1,15 +1,30 @@
 extern crate std;
-use core::cmp::min;
 use std::println;
 
-use crate::{AuctionContract, AuctionContractClient, token, types::Status};
-use soroban_sdk::{Env, testutils::Address as _, Address, testutils::Ledger as Ledger};
+use auction_pricing::{bid_at, ScheduleParams};
+use crate::{AuctionContract, AuctionContractClient, MIN_TTL,
+            migration::{AuctionDataV0, DataV1, LegacyDataKey},
+            events::{AmendedEvent, BidInfoEvent, ClosedEvent, CreatedEvent, OperatorEvent, PausedEvent, ResetEvent, ResumedEvent, SoldEvent, EVENT_VERSION}, TTL_BUFFER, TTL_THRESHOLD, token, types::{AuctionKey, Balances, DataKey, Error, EscrowTerms, Sale, SaleRecord, SellOrder, State, Status}};
+use crate::testutils::{AuctionParams, Harness, MockAccount, MockAccountClient, MockToken, MockTokenClient, Op};
+use proptest::prelude::*;
+use soroban_env_host::storage::AccessType;
+use soroban_sdk::xdr::{Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, InvokeContractArgs, LedgerKey, Limits, ScAddress, ScVal,
+                       SorobanAddressCredentials, SorobanAuthorizationEntry, SorobanAuthorizedFunction, SorobanAuthorizedInvocation,
+                       SorobanCredentials, WriteXdr};
+use std::rc::Rc;
+use soroban_sdk::{Env, testutils::{Address as _, AuthorizedFunction, EnvTestConfig, MockAuth, MockAuthInvoke}, Address, Bytes, symbol_short, vec, IntoVal, Symbol, TryFromVal, Val, testutils::Events as _, testutils::Ledger as Ledger, testutils::storage::{Instance as _, Persistent as _}};
 
 
 fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
     token::StellarAssetClient::new(e, &e.register_stellar_asset_contract(admin.clone()))
 }
 
+// Register the auction contract at the address it gets when `deployer` deploys it with `salt`, so `deployer` can initialise it
+fn deploy_auction_contract(e: &Env, deployer: &Address, salt: &soroban_sdk::BytesN<32>) -> Address {
+    let contract_id = e.deployer().with_address(deployer.clone(), salt.clone()).deployed_address();
+    e.register_contract(Some(&contract_id), AuctionContract)
+}
+
 fn calculate_bid (env: &Env,
                  start_bid: i128,
                  start_ledger: u32, 
@@ -17,13 +32,14 @@ fn calculate_bid (env: &Env,
                  incr_times: u32 , 
                  incr_interval: u32
                 ) -> i128 {
-    let current_ledger = env.ledger().sequence();
-    let ledgers_passed = current_ledger - start_ledger;
-    let times_increased = min(ledgers_passed / incr_interval, incr_times);
-    
-    let current_bid = start_bid + (incr_amount * times_increased as i128);
-
-    current_bid
+    let params = ScheduleParams {
+        start_ledger,
+        start_bid,
+        incr_amount,
+        incr_interval,
+        incr_times,
+    };
+    bid_at(&params, env.ledger().sequence()).current_bid
 }
 
 
@@ -75,7 +91,10 @@ fn test_setup(){
         &test_starting_bid, 
         &test_bid_incr_amount, 
         &test_bid_incr_times,
-        &test_bid_incr_interval);
+        &test_bid_incr_interval,
+        &None,
+        &None,
+        &0);
     
     let test_max_bid = test_starting_bid + (test_bid_incr_amount * test_bid_incr_times as i128);
 
@@ -89,7 +108,10 @@ fn test_setup(){
         &10, 
         &10, 
         &10,
-        &10);
+        &10,
+        &None,
+        &None,
+        &0);
     assert_eq!(test_setup, Status::AlreadyInitialised);
     
     println!("Auction created.");
@@ -135,7 +157,7 @@ fn test_setup(){
         assert_eq!(current_bid, test_get_bid_info.current_bid);
         println!("Bid info verified: bid is {} at ledger {}", current_bid, env.ledger().sequence());
     }
-    println!("");
+    println!();
     // Revert the ledger sequence back to 0, to allow testing the sale at specific ledgers
     env.ledger().with_mut(|li|li.sequence_number = 0);
 
@@ -155,7 +177,7 @@ fn test_setup(){
     println!("The contract has {} of the auction asset and {} of the counter-asset.", asset_token.balance(&contract_id), counter_asset_token.balance(&contract_id));
 
     // Sell the asset to the auction and verify the correct status is returned. Log the sell price
-    let test_sell = auction_client.sell_token(&seller);
+    let test_sell = auction_client.sell_token(&seller, &0, &None);
     assert_eq!(test_sell, Status::Fulfilled);
 
     let sell_price = current_bid;
@@ -172,10 +194,10 @@ fn test_setup(){
     println!("Sold the asset at ledger {} for {}. ", env.ledger().sequence(), sell_price);
     println!("The seller now has {} of the auction asset and {} of the counter-asset.",  asset_token.balance(&seller), counter_asset_token.balance(&seller));
     println!("The contract now has {} of the auction asset and {} of the counter-asset.", asset_token.balance(&contract_id), counter_asset_token.balance(&contract_id));
-    println!("");
+    println!();
     
     // Try to iniate sale again
-    let test_sell = auction_client.sell_token(&seller);
+    let test_sell = auction_client.sell_token(&seller, &0, &None);
     assert_eq!(test_sell, Status::NotRunning);
 
     // Check if the balances are not changed
@@ -187,7 +209,7 @@ fn test_setup(){
 
     println!("Preparing to close the auction");
     println!("The host has {} of the auction asset and {} of the counter-asset.", asset_token.balance(&host), counter_asset_token.balance(&host));
-    println!("");
+    println!();
 
     // Attempt to close the auction
     let test_close = auction_client.close_auction();
@@ -206,7 +228,7 @@ fn test_setup(){
     println!("The contract now has {} of the auction asset and {} of the counter-asset.", asset_token.balance(&contract_id), counter_asset_token.balance(&contract_id));
     println!("The host now has {} of the auction asset and {} of the counter-asset.", asset_token.balance(&host), counter_asset_token.balance(&host));
 
-    println!("");
+    println!();
 
     // Attempt to close the auction a second time
     let test_close = auction_client.close_auction();
@@ -221,5 +243,1588 @@ fn test_setup(){
     assert_eq!(counter_asset_token.balance(&host), 100_000_000 - sell_price);
 
     println!("Tests completed!");
-    println!("");
+    println!();
+}
+
+// A contract with an admin, an arbiter, and mock tokens that can be made to fail, charge a fee or re-enter.
+// The seller holds one auction token, and the buyer enough counter token for any auction
+struct AuctionTest<'a> {
+    env: Env,
+    contract_id: Address,
+    admin: Address,
+    salt: soroban_sdk::BytesN<32>,
+    buyer: Address,
+    seller: Address,
+    arbiter: Address,
+    token: MockTokenClient<'a>,
+    counter_token: MockTokenClient<'a>,
+    auction_client: AuctionContractClient<'a>,
+}
+
+fn setup_test<'a>() -> AuctionTest<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let token = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+    token.mint(&seller, &1);
+    let counter_token = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+    counter_token.mint(&buyer, &100_000);
+
+    let admin = Address::generate(&env);
+    let salt = soroban_sdk::BytesN::from_array(&env, &[1; 32]);
+    let contract_id = deploy_auction_contract(&env, &admin, &salt);
+    AuctionTest {
+        auction_client: AuctionContractClient::new(&env, &contract_id),
+        admin,
+        salt,
+        arbiter: Address::generate(&env),
+        env,
+        contract_id,
+        buyer,
+        seller,
+        token,
+        counter_token,
+    }
+}
+
+impl AuctionTest<'_> {
+    // Set up an auction from 1000 to 2000 in 10 increases of 10 ledgers, with an optional dispute window of 50 ledgers
+    fn setup(&self, escrow: bool, start_ledger: Option<u32>, cancel_notice: u32) -> Result<Status, Error> {
+        let escrow = escrow.then(|| EscrowTerms { arbiter: self.arbiter.clone(), dispute_window: 50 });
+        outcome(self.auction_client.try_setup_auction(&self.buyer, &self.token.address, &self.counter_token.address,
+                                                      &1000, &100, &10, &10, &escrow, &start_ledger, &cancel_notice))
+    }
+
+    fn advance(&self, ledgers: u32) {
+        self.env.ledger().with_mut(|li|li.sequence_number += ledgers);
+    }
+}
+
+// The status or contract error returned by a call
+fn outcome<E: core::fmt::Debug>(result: Result<Result<Status, E>, Result<Error, soroban_sdk::InvokeError>>) -> Result<Status, Error> {
+    match result {
+        Ok(status) => Ok(status.unwrap()),
+        Err(error) => Err(error.unwrap()),
+    }
+}
+
+#[test]
+fn test_escrow_finalize(){
+    let t = setup_test();
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+
+    // Sell at the starting bid; the payment is held by the contract
+    assert_eq!(t.auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    assert_eq!(t.token.balance(&t.contract_id), 1);
+    assert_eq!(t.counter_token.balance(&t.seller), 0);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+
+    // The buyer can not close the auction while the sale is pending
+    assert!(t.auction_client.try_close_auction().is_err());
+
+    // Nobody can finalize before the window has passed
+    t.env.ledger().with_mut(|li|li.sequence_number += 49);
+    assert_eq!(t.auction_client.finalize(), Status::DisputeWindowOpen);
+
+    t.env.ledger().with_mut(|li|li.sequence_number += 1);
+    assert_eq!(t.auction_client.raise_dispute(), Status::DisputeWindowClosed);
+    assert_eq!(t.auction_client.finalize(), Status::Released);
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+    assert_eq!(t.auction_client.finalize(), Status::NotPendingRelease);
+
+    // The buyer closes the auction as usual
+    assert_eq!(t.auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.token.balance(&t.buyer), 1);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1000);
+}
+
+#[test]
+fn test_escrow_dispute_release(){
+    let t = setup_test();
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+
+    assert_eq!(t.auction_client.resolve_dispute(&true), Status::NotDisputed);
+    assert_eq!(t.auction_client.raise_dispute(), Status::NotPendingRelease);
+
+    t.env.ledger().with_mut(|li|li.sequence_number += 25);
+    assert_eq!(t.auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    assert_eq!(t.auction_client.raise_dispute(), Status::DisputeRaised);
+    assert_eq!(t.env.as_contract(&t.contract_id, || {
+        t.env.storage().persistent().get::<_, State>(&crate::types::AuctionKey::State(1)).unwrap()
+    }), State::Disputed);
+
+    // A disputed sale can not be finalized, even after the window. Its price is reported, not the schedule
+    t.env.ledger().with_mut(|li|li.sequence_number += 100);
+    assert_eq!(t.auction_client.finalize(), Status::NotPendingRelease);
+    let bid_info = t.auction_client.get_bid_info_at(&u32::MAX);
+    assert_eq!((bid_info.current_bid, bid_info.max_bid, bid_info.max_bid_ledger), (1200, 1200, 25));
+
+    assert_eq!(t.auction_client.resolve_dispute(&true), Status::Released);
+    assert_eq!(t.counter_token.balance(&t.seller), 1200);
+    assert_eq!(t.auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.token.balance(&t.buyer), 1);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1200);
+}
+
+#[test]
+fn test_escrow_dispute_refund(){
+    let t = setup_test();
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+
+    assert_eq!(t.auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    assert_eq!(t.auction_client.raise_dispute(), Status::DisputeRaised);
+    assert_eq!(t.auction_client.resolve_dispute(&false), Status::Refunded);
+
+    // The resolution was authorised by the arbiter
+    assert_eq!(t.env.auths()[0].0, t.arbiter);
+
+    // The token goes back to the seller and the escrow back to the buyer
+    assert_eq!(t.token.balance(&t.seller), 1);
+    assert_eq!(t.token.balance(&t.contract_id), 0);
+    assert_eq!(t.counter_token.balance(&t.seller), 0);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 0);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+
+    assert_eq!(t.auction_client.close_auction(), Status::AlreadyClosed);
+}
+
+#[test]
+fn test_pause_resume(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let auction_start_ledger = t.env.ledger().sequence();
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(auction_client.resume_auction(&t.buyer), Status::NotPaused);
+
+    // Pause halfway through the third interval, at a bid of 1200
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 25);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::NotRunning);
+
+    // While paused the bid is frozen, and the token can not be sold
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 125);
+    let bid_info = auction_client.get_bid_info();
+    assert_eq!(bid_info.state, State::Paused);
+    assert_eq!(bid_info.current_bid, 1200);
+    assert_eq!(bid_info.ledgers_to_next_increase, 5);
+    assert_eq!(bid_info.max_bid_ledger, auction_start_ledger + 200);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Paused);
+
+    // After resuming, the schedule continues where it was paused
+    assert_eq!(auction_client.resume_auction(&t.buyer), Status::Resumed);
+    let bid_info = auction_client.get_bid_info();
+    assert_eq!(bid_info.state, State::Running);
+    assert_eq!(bid_info.current_bid, 1200);
+    assert_eq!(bid_info.next_bid_ledger, auction_start_ledger + 130);
+
+    t.env.ledger().with_mut(|li|li.sequence_number += 5);
+    assert_eq!(auction_client.get_bid_info().current_bid, 1300);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(t.counter_token.balance(&t.seller), 1300);
+}
+
+#[test]
+fn test_abort_paused(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+
+    // The buyer can still abort a paused auction and get the escrow back
+    assert_eq!(auction_client.close_auction(), Status::Aborted);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+}
+
+#[test]
+fn test_amend_auction(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let auction_start_ledger = t.env.ledger().sequence();
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+
+    // At ledger 35 the bid is 1300, with 7 increases to go
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 35);
+
+    // Amendments that lower the price path are rejected
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1299, &100, &0), Status::AmendmentLowersPrice);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1300, &99, &5), Status::AmendmentLowersPrice);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+
+    // Raise the bid to 1500, increase by 150 and add 3 increases: a new maximum of 1500 + 150 * 10
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1500, &150, &3), Status::Amended);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 3000);
+    assert_eq!(t.counter_token.balance(&t.buyer), 97_000);
+
+    // The current interval is kept, so the next increase is still at ledger 40
+    let bid_info = auction_client.get_bid_info();
+    assert_eq!(bid_info.current_bid, 1500);
+    assert_eq!(bid_info.next_bid, 1650);
+    assert_eq!(bid_info.next_bid_ledger, auction_start_ledger + 40);
+    assert_eq!(bid_info.max_bid, 3000);
+    assert_eq!(bid_info.max_bid_ledger, auction_start_ledger + 130);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 40);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(t.counter_token.balance(&t.seller), 1650);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &2000, &200, &0), Status::NotRunning);
+
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1650);
+}
+
+#[test]
+fn test_amend_after_max_bid(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let auction_start_ledger = t.env.ledger().sequence();
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // Long after the maximum bid was reached, extra increases restart from the interval of the current ledger
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 255);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &2000, &100, &2), Status::Amended);
+    let bid_info = auction_client.get_bid_info();
+    assert_eq!(bid_info.current_bid, 2000);
+    assert_eq!(bid_info.next_bid_ledger, auction_start_ledger + 260);
+    assert_eq!(bid_info.max_bid, 2200);
+    assert_eq!(bid_info.max_bid_ledger, auction_start_ledger + 270);
+
+    // Without extra increases, the bid stays at the new maximum
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 300);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &2500, &100, &0), Status::Amended);
+    assert_eq!(auction_client.get_bid_info().current_bid, 2500);
+}
+
+#[test]
+fn test_scheduled_start(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    t.env.ledger().with_mut(|li|li.sequence_number = 1000);
+
+    // An auction can not be scheduled in the past
+    assert_eq!(t.setup(false, Some(999), 0), Ok(Status::StartLedgerInPast));
+
+    // Announce the auction 500 ledgers in advance; the escrow is taken right away
+    assert_eq!(t.setup(false, Some(1500), 0), Ok(Status::Scheduled));
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+
+    let bid_info = auction_client.get_bid_info();
+    assert_eq!(bid_info.state, State::NotStarted);
+    assert_eq!(bid_info.start_ledger, 1500);
+    assert_eq!(bid_info.current_bid, 1000);
+    assert_eq!(bid_info.next_bid_ledger, 1510);
+    assert_eq!(bid_info.ledgers_to_next_increase, 510);
+    assert_eq!(bid_info.max_bid_ledger, 1600);
+
+    // Nothing can be sold and the auction can not be paused until the start ledger
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::NotStarted);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::NotStarted);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = 1500);
+    assert_eq!(auction_client.get_bid_info().state, State::Running);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = 1525);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(t.counter_token.balance(&t.seller), 1200);
+}
+
+#[test]
+fn test_cancel_notice(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let auction_start_ledger = t.env.ledger().sequence();
+    assert_eq!(t.setup(false, None, 20), Ok(Status::Started));
+
+    // Closing a running auction starts the notice period instead of refunding right away
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 15);
+    assert_eq!(auction_client.close_auction(), Status::Cancelling);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+    assert_eq!(auction_client.get_bid_info().state, State::Cancelling);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::NotRunning);
+
+    // The refund can not be executed before the notice has passed
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 34);
+    assert_eq!(auction_client.close_auction(), Status::NoticePeriod);
+
+    // Sales during the notice honour the current bid
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(t.counter_token.balance(&t.seller), 1300);
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1300);
+}
+
+#[test]
+fn test_cancel_notice_refund(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let auction_start_ledger = t.env.ledger().sequence();
+    assert_eq!(t.setup(false, None, 20), Ok(Status::Started));
+
+    // Pausing does not skip the notice: the auction resumes and then runs out its notice
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 15);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 45);
+    assert_eq!(auction_client.close_auction(), Status::Cancelling);
+
+    // The shifted start is published before the notice, so indexers follow the bid during it
+    let events = t.env.events().all();
+    let (_, topics, data) = events.get(events.len() - 2).unwrap();
+    assert_eq!(topics, (symbol_short!("resumed"), 1_u32, t.buyer.clone()).into_val(&t.env));
+    let resumed = ResumedEvent::try_from_val(&t.env, &data).unwrap();
+    assert_eq!(resumed, ResumedEvent { version: EVENT_VERSION, auction_start_ledger: auction_start_ledger + 30 });
+    assert_eq!(auction_client.get_bid_info().current_bid, 1100);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = auction_start_ledger + 65);
+    assert_eq!(auction_client.close_auction(), Status::Aborted);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+}
+
+#[test]
+fn test_tracked_escrow_and_rescue(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let donor = Address::generate(&t.env);
+    t.counter_token.mint(&donor, &500);
+
+    // Rescuing needs an admin, which can only be set once
+    assert!(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &1).is_err());
+    assert_eq!(auction_client.initialize(&t.admin, &t.salt), Status::AdminSet);
+    assert!(auction_client.try_initialize(&t.buyer, &t.salt).is_err());
+
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // Somebody sends tokens to the contract by accident
+    t.counter_token.transfer(&donor, &t.contract_id, &500);
+    t.token.mint(&t.contract_id, &3);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2500);
+
+    // The escrow can not be rescued, only the accidental transfer
+    assert_eq!(auction_client.rescue_tokens(&t.counter_token.address, &donor, &501), Status::ExceedsUntracked);
+    assert_eq!(auction_client.rescue_tokens(&t.counter_token.address, &donor, &200), Status::Rescued);
+    assert_eq!(t.counter_token.balance(&donor), 200);
+
+    // Selling and closing only pays out what the auction holds
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1000);
+    assert_eq!(t.token.balance(&t.buyer), 1);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 300);
+    assert_eq!(t.token.balance(&t.contract_id), 3);
+
+    // Once the auction is closed, the remainder can be rescued
+    assert_eq!(auction_client.rescue_tokens(&t.counter_token.address, &donor, &300), Status::Rescued);
+    assert_eq!(auction_client.rescue_tokens(&t.token.address, &t.admin, &3), Status::Rescued);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 0);
+    assert_eq!(t.token.balance(&t.contract_id), 0);
+}
+
+#[test]
+fn test_fee_on_transfer_token(){
+    let t = setup_test();
+
+    // A token that burns a fee of 10 stroops on every transfer
+    t.counter_token.set_fee(&10);
+
+    // The escrow would arrive 10 stroops short of the maximum bid, so the setup is rejected and rolled back
+    assert_eq!(t.setup(false, None, 0), Err(Error::TransferAmountMismatch));
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 0);
+
+    // Selling a token of which nothing arrives is rejected as well
+    t.counter_token.set_fee(&0);
+    t.token.set_fee(&1);
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(t.auction_client.try_sell_token(&t.seller, &0, &None), Err(Ok(Error::TransferAmountMismatch)));
+    assert_eq!(t.token.balance(&t.seller), 1);
+    assert_eq!(t.counter_token.balance(&t.seller), 0);
+}
+
+// The contract has no lock of its own: the host refuses a token's call back into the auction that called it
+#[test]
+fn test_host_blocks_reentry(){
+    let t = setup_test();
+    t.token.mint(&t.seller, &1);
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // The token calls sell_token again while the contract pulls it from the seller, and the host fails that call
+    t.token.set_reentry(&t.contract_id, &Symbol::new(&t.env, "sell_token"), &(t.seller.clone(), 0_i128, None::<u32>).into_val(&t.env));
+    assert_eq!(t.auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert!(!t.token.reentered());
+
+    // The seller was paid once, and the auction only took one token
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 1000);
+    assert_eq!(t.token.balance(&t.seller), 1);
+    assert_eq!(t.token.balance(&t.contract_id), 1);
+}
+
+#[test]
+fn test_setup_validation(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    t.env.ledger().with_mut(|li|li.sequence_number = 100);
+    t.counter_token.mint(&t.buyer, &(i128::MAX - 100_000));
+    let max_ttl = t.env.as_contract(&t.contract_id, || t.env.storage().max_ttl());
+
+    // (buyer, auction token, starting bid, increase amount, increase times, increase interval, dispute window, start ledger, notice, expected)
+    let cases = [
+        (t.contract_id.clone(), t.token.address.clone(), 1000, 100, 10, 10, None, None, 0, Status::BuyerIsContract),
+        (t.buyer.clone(), t.counter_token.address.clone(), 1000, 100, 10, 10, None, None, 0, Status::IdenticalTokens),
+        (t.buyer.clone(), t.token.address.clone(), 0, 100, 10, 10, None, None, 0, Status::BidMustBePositive),
+        (t.buyer.clone(), t.token.address.clone(), 1000, -1, 10, 10, None, None, 0, Status::BidMustBePositive),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 10, 0, None, None, 0, Status::ZeroInterval),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 10, 10, None, Some(99), 0, Status::StartLedgerInPast),
+        (t.buyer.clone(), t.token.address.clone(), i128::MAX, 1, 1, 10, None, None, 0, Status::Overflow),
+        (t.buyer.clone(), t.token.address.clone(), 1000, i128::MAX / 2, 3, 10, None, None, 0, Status::Overflow),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, u32::MAX, 2, None, None, 0, Status::Overflow),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 1, u32::MAX - 50, None, None, 0, Status::Overflow),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 1, 10, None, Some(u32::MAX - 5), 0, Status::Overflow),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 1, max_ttl, None, None, 0, Status::DurationTooLong),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 10, 10, None, Some(100 + max_ttl), 0, Status::DurationTooLong),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 10, 10, Some(max_ttl - TTL_BUFFER + 1), None, 0, Status::DurationTooLong),
+        (t.buyer.clone(), t.token.address.clone(), 1000, 100, 10, 10, None, None, max_ttl - TTL_BUFFER + 1, Status::DurationTooLong),
+    ];
+
+    for (test_buyer, test_asset, starting_bid, incr_amount, incr_times, incr_interval, dispute_window, start_ledger, notice, expected) in cases {
+        let escrow = dispute_window.map(|dispute_window| EscrowTerms { arbiter: Address::generate(&t.env), dispute_window });
+        let test_setup = auction_client.setup_auction(&test_buyer, &test_asset, &t.counter_token.address,
+            &starting_bid, &incr_amount, &incr_times, &incr_interval, &escrow, &start_ledger, &notice);
+        assert_eq!(test_setup, expected);
+
+        // A rejected setup takes no escrow and leaves the contract uninitialised
+        assert_eq!(t.counter_token.balance(&t.contract_id), 0);
+    }
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // Amendments are checked in the same way
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1000, &i128::MAX, &0), Status::Overflow);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1000, &100, &u32::MAX), Status::Overflow);
+    assert_eq!(auction_client.amend_auction(&t.buyer, &1000, &100, &(max_ttl / 10)), Status::DurationTooLong);
+}
+
+#[test]
+fn test_ttl_extension(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let ttl = || t.env.as_contract(&t.contract_id, || {
+        (t.env.storage().instance().get_ttl(), t.env.storage().persistent().get_ttl(&AuctionKey::Data(1)))
+    });
+
+    // An auction that lasts longer than the minimum TTL is kept live for its whole duration
+    auction_client.setup_auction(&t.buyer, &t.token.address, &t.counter_token.address, &1000, &100, &10, &(MIN_TTL / 5), &None, &None, &0);
+    assert_eq!(ttl(), (2 * MIN_TTL + TTL_BUFFER, 2 * MIN_TTL + TTL_BUFFER));
+
+    t.env.ledger().with_mut(|li|li.sequence_number += 2 * MIN_TTL);
+
+    // The token is sold at the maximum bid, but the buyer does not close the auction for a long time.
+    // Anybody can bump the auction, so it stays live until the buyer returns
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(ttl(), (MIN_TTL, MIN_TTL));
+    for _ in 0..4 {
+        t.env.ledger().with_mut(|li|li.sequence_number += MIN_TTL - 1);
+        assert_eq!(auction_client.bump(), Status::Bumped);
+        assert_eq!(ttl(), (MIN_TTL, MIN_TTL));
+    }
+
+    // A bump within the threshold does not extend the TTL again
+    t.env.ledger().with_mut(|li|li.sequence_number += TTL_THRESHOLD - 1);
+    assert_eq!(auction_client.bump(), Status::Bumped);
+    assert_eq!(ttl(), (MIN_TTL - TTL_THRESHOLD + 1, MIN_TTL - TTL_THRESHOLD + 1));
+
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(t.token.balance(&t.buyer), 1);
+}
+
+#[test]
+fn test_ttl_small_network_maximum(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    t.env.ledger().with_mut(|li|li.max_entry_ttl = 5000);
+
+    let ttl = || t.env.as_contract(&t.contract_id, || {
+        (t.env.storage().instance().get_ttl(), t.env.storage().persistent().get_ttl(&AuctionKey::Data(1)))
+    });
+
+    // A network maximum below the threshold caps the TTL, and every call extends it again
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(ttl(), (4999, 4999));
+    t.env.ledger().with_mut(|li|li.sequence_number += 50);
+    assert_eq!(auction_client.bump(), Status::Bumped);
+    assert_eq!(ttl(), (4999, 4999));
+
+    // The sale extends the TTL once, and publishes only the sale
+    let published = || t.env.events().all().iter().filter(|(id, _, _)| *id == t.contract_id).collect::<std::vec::Vec<_>>();
+    let events = published().len();
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    let published = published();
+    assert_eq!(published.len(), events + 1);
+    let (_, topics, _) = published.last().unwrap().clone();
+    assert_eq!(topics, (symbol_short!("sold"), 1_u32, t.buyer.clone()).into_val(&t.env));
+}
+
+#[test]
+fn test_ttl_paused_auction(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // Pause the auction far beyond its original TTL, while the sellers' frontend keeps querying the bid
+    t.env.ledger().with_mut(|li|li.sequence_number += 25);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+    for _ in 0..3 {
+        t.env.ledger().with_mut(|li|li.sequence_number += MIN_TTL - 100);
+        assert_eq!(auction_client.get_bid_info().current_bid, 1200);
+    }
+
+    assert_eq!(auction_client.resume_auction(&t.buyer), Status::Resumed);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    assert_eq!(t.counter_token.balance(&t.seller), 1200);
+}
+
+#[test]
+fn test_ttl_dispute_window_and_cancel_notice(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    let ttl = || t.env.as_contract(&t.contract_id, || {
+        (t.env.storage().instance().get_ttl(), t.env.storage().persistent().get_ttl(&AuctionKey::Data(1)))
+    });
+
+    // A dispute window longer than the minimum TTL keeps the sale live until it can be finalized
+    let escrow = EscrowTerms { arbiter: t.arbiter.clone(), dispute_window: 2 * MIN_TTL };
+    auction_client.setup_auction(&t.buyer, &t.token.address, &t.counter_token.address, &1000, &100, &10, &10, &Some(escrow), &None, &0);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    assert_eq!(ttl(), (2 * MIN_TTL + TTL_BUFFER, 2 * MIN_TTL + TTL_BUFFER));
+    t.env.ledger().with_mut(|li|li.sequence_number += 2 * MIN_TTL);
+    assert_eq!(auction_client.finalize(), Status::Released);
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+
+    // A cancellation notice longer than the minimum TTL keeps the auction live until it can be refunded
+    let ttl = || t.env.as_contract(&t.contract_id, || {
+        (t.env.storage().instance().get_ttl(), t.env.storage().persistent().get_ttl(&AuctionKey::Data(2)))
+    });
+    assert_eq!(t.setup(false, None, 2 * MIN_TTL), Ok(Status::Started));
+    assert_eq!(auction_client.close_auction(), Status::Cancelling);
+    assert_eq!(ttl(), (2 * MIN_TTL + TTL_BUFFER, 2 * MIN_TTL + TTL_BUFFER));
+    t.env.ledger().with_mut(|li|li.sequence_number += 2 * MIN_TTL);
+    assert_eq!(auction_client.close_auction(), Status::Aborted);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 1000);
+}
+
+#[test]
+fn test_view_entrypoints(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    // Without an auction, the views report NotInitialised instead of panicking
+    assert_eq!(auction_client.try_get_state(), Err(Ok(Error::NotInitialised)));
+    assert_eq!(auction_client.try_get_auction_summary(), Err(Ok(Error::NotInitialised)));
+
+    assert_eq!(t.setup(false, Some(50), 0), Ok(Status::Scheduled));
+    let auction_data = auction_client.get_auction_data();
+    assert_eq!(auction_data.buyer, t.buyer);
+    assert_eq!(auction_data.bid_max_amount, 2000);
+
+    // The schedule can be inspected for any ledger, before and after the start, without publishing events
+    let events = t.env.events().all().len();
+    assert_eq!(auction_client.get_state(), State::NotStarted);
+    let bid_info = auction_client.get_bid_info_at(&0);
+    assert_eq!((bid_info.current_bid, bid_info.state), (1000, State::NotStarted));
+    let bid_info = auction_client.get_bid_info_at(&75);
+    assert_eq!((bid_info.current_bid, bid_info.next_bid, bid_info.next_bid_ledger, bid_info.state), (1200, 1300, 80, State::Running));
+    let bid_info = auction_client.get_bid_info_at(&1000);
+    assert_eq!((bid_info.current_bid, bid_info.ledgers_to_next_increase), (2000, 0));
+    assert_eq!(t.env.events().all().len(), events);
+
+    // While paused, ledgers before the pause follow the original schedule, later ones are frozen
+    t.env.ledger().with_mut(|li|li.sequence_number = 85);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+    let events = t.env.events().all().len();
+    assert_eq!(auction_client.get_bid_info_at(&60).current_bid, 1100);
+    assert_eq!(auction_client.get_bid_info_at(&500).current_bid, 1300);
+    let bid_info = auction_client.get_bid_info_at(&u32::MAX);
+    assert_eq!((bid_info.current_bid, bid_info.next_bid_ledger, bid_info.max_bid_ledger), (1300, u32::MAX, u32::MAX));
+    assert_eq!(auction_client.get_state(), State::Paused);
+    assert_eq!(t.env.events().all().len(), events);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = 100);
+    assert_eq!(auction_client.resume_auction(&t.buyer), Status::Resumed);
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+
+    // A Fulfilled auction reports its sale price
+    let events = t.env.events().all().len();
+    let summary = auction_client.get_auction_summary();
+    assert_eq!(t.env.events().all().len(), events);
+    assert_eq!(summary.id, 1);
+    assert_eq!(summary.state, State::Fulfilled);
+    assert_eq!(summary.sale_price, Some(1300));
+    assert_eq!(summary.balances, Balances { counter_token: 700, token: 1 });
+    assert_eq!(summary.data.auction_start_ledger, 65);
+
+    // So does a Closed auction, which get_bid_info no longer reports on
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    let summary = auction_client.get_auction_summary();
+    assert_eq!((summary.state, summary.sale_price, summary.balances), (State::Closed, Some(1300), Balances::default()));
+    assert_eq!(auction_client.get_auction_data().buyer, auction_data.buyer);
+
+    // Once sold, the bid information reports the sale price at the ledger of the sale, instead of the schedule
+    for ledger in [0, 100, 1000] {
+        let bid_info = auction_client.get_bid_info_at(&ledger);
+        assert_eq!((bid_info.current_bid, bid_info.next_bid, bid_info.max_bid), (1300, 1300, 1300));
+        assert_eq!((bid_info.max_bid_ledger, bid_info.next_bid_ledger, bid_info.state), (100, 0, State::Closed));
+    }
+}
+
+#[test]
+fn test_sale_record_and_history(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    assert_eq!(auction_client.get_history(&0, &10).len(), 0);
+
+    // The first auction is sold, its sale is recorded when it is fulfilled
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    t.env.ledger().with_mut(|li|{
+        li.sequence_number = 25;
+        li.timestamp = 12345;
+    });
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    let sale_record = SaleRecord { seller: t.seller.clone(), price: 1200, ledger: 25, timestamp: 12345 };
+    assert_eq!(t.env.as_contract(&t.contract_id, || t.env.storage().persistent().get(&AuctionKey::Sale(1))), Some(sale_record.clone()));
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+
+    // The history only holds auctions that have been reset
+    assert_eq!(auction_client.get_history(&0, &10).len(), 0);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+
+    // The second auction is aborted without a sale
+    auction_client.setup_auction(&t.buyer, &t.token.address, &t.counter_token.address, &500, &50, &10, &10, &None, &None, &0);
+    assert_eq!(auction_client.close_auction(), Status::Aborted);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+
+    // The third auction is running, and is not part of the history yet
+    auction_client.setup_auction(&t.buyer, &t.token.address, &t.counter_token.address, &700, &50, &10, &10, &None, &None, &0);
+
+    let history = auction_client.get_history(&0, &10);
+    assert_eq!(history.len(), 2);
+    let first = history.get(0).unwrap();
+    assert_eq!((first.id, first.state, first.sale), (1, State::Closed, Sale::Sold(sale_record)));
+    assert_eq!(first.data.bid_start_amount, 1000);
+    let second = history.get(1).unwrap();
+    assert_eq!((second.id, second.state, second.sale), (2, State::Aborted, Sale::Unsold));
+    assert_eq!(second.data.bid_start_amount, 500);
+
+    // Pages start at the given sequence number
+    let page = auction_client.get_history(&2, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().id, 2);
+    assert_eq!(auction_client.get_history(&1, &1).get(0).unwrap().id, 1);
+    assert_eq!(auction_client.get_history(&3, &10).len(), 0);
+    assert_eq!(auction_client.get_history(&1, &0).len(), 0);
+
+    // The history outlives the TTL of the running auction, which is kept live by bumping it within the threshold
+    for _ in 0..6 {
+        t.env.ledger().with_mut(|li|li.sequence_number += MIN_TTL - TTL_THRESHOLD + 1);
+        assert_eq!(auction_client.bump(), Status::Bumped);
+    }
+    assert_eq!(auction_client.get_history(&0, &10), history);
+
+    // A running auction without a count returns an empty page
+    t.env.as_contract(&t.contract_id, || t.env.storage().instance().remove(&DataKey::AuctionCount));
+    assert_eq!(auction_client.get_history(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_event_schema(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    // Every auction event has the name, auction id and buyer as topics, and a versioned payload
+    let last_event = |name: Symbol| -> Val {
+        let (contract, topics, data) = t.env.events().all().last().unwrap();
+        assert_eq!(contract, t.contract_id);
+        assert_eq!(topics, (name, 1_u32, t.buyer.clone()).into_val(&t.env));
+        data
+    };
+
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    let created = CreatedEvent::try_from_val(&t.env, &last_event(symbol_short!("created"))).unwrap();
+    assert_eq!(created.version, EVENT_VERSION);
+    assert_eq!(created.data, auction_client.get_auction_data());
+    assert_eq!(created.received, 2000);
+
+    t.env.ledger().with_mut(|li|li.sequence_number = 20);
+    auction_client.amend_auction(&t.buyer, &1200, &200, &0);
+    let amended = AmendedEvent::try_from_val(&t.env, &last_event(symbol_short!("amended"))).unwrap();
+    assert_eq!((amended.version, amended.data.bid_start_amount, amended.data.bid_incr_amount), (EVENT_VERSION, 1200, 200));
+    assert_eq!(amended.received, 800);
+
+    auction_client.pause_auction(&t.buyer);
+    let paused = PausedEvent::try_from_val(&t.env, &last_event(symbol_short!("paused"))).unwrap();
+    assert_eq!(paused, PausedEvent { version: EVENT_VERSION, paused_ledger: 20 });
+
+    t.env.ledger().with_mut(|li|li.sequence_number = 30);
+    auction_client.resume_auction(&t.buyer);
+    let resumed = ResumedEvent::try_from_val(&t.env, &last_event(symbol_short!("resumed"))).unwrap();
+    assert_eq!(resumed, ResumedEvent { version: EVENT_VERSION, auction_start_ledger: 30 });
+
+    let operator = Address::generate(&t.env);
+    auction_client.set_operator(&Some(operator.clone()));
+    let appointed = OperatorEvent::try_from_val(&t.env, &last_event(symbol_short!("operator"))).unwrap();
+    assert_eq!(appointed, OperatorEvent { version: EVENT_VERSION, operator, appointed: true });
+
+    auction_client.get_bid_info();
+    let bid_info = BidInfoEvent::try_from_val(&t.env, &last_event(symbol_short!("bid_info"))).unwrap();
+    assert_eq!(bid_info.bid_info.current_bid, 1200);
+
+    auction_client.sell_token(&t.seller, &0, &None);
+    let sold = SoldEvent::try_from_val(&t.env, &last_event(symbol_short!("sold"))).unwrap();
+    assert_eq!(sold.sale, SaleRecord { seller: t.seller.clone(), price: 1200, ledger: 30, timestamp: 0 });
+    assert_eq!(sold.token_received, 1);
+
+    auction_client.close_auction();
+    let closed = ClosedEvent::try_from_val(&t.env, &last_event(symbol_short!("closed"))).unwrap();
+    assert_eq!(closed, ClosedEvent { version: EVENT_VERSION, token_amount: 1, counter_token_amount: 1600 });
+
+    auction_client.reset_auction();
+    let reset = ResetEvent::try_from_val(&t.env, &last_event(symbol_short!("reset"))).unwrap();
+    assert_eq!(reset.version, EVENT_VERSION);
+}
+
+#[test]
+fn test_upgrade_and_migrate(){
+    // Run an auction in each of the older storage layouts through an upgrade and migration
+    for legacy_version in 0..2_u32 {
+        let t = setup_test();
+        let auction_client = &t.auction_client;
+
+        // The older version set up an auction at ledger 0, which has been running for 35 ledgers.
+        // Somebody also sent 50 of the counter token to the contract by accident
+        let legacy_data = DataV1 {
+            buyer: t.buyer.clone(),
+            token: t.token.address.clone(),
+            counter_token: t.counter_token.address.clone(),
+            auction_start_ledger: 0,
+            bid_start_amount: 1000,
+            bid_incr_amount: 100,
+            bid_incr_interval: 10,
+            bid_incr_times: 10,
+            bid_max_amount: 2000,
+        };
+        t.counter_token.mint(&t.contract_id, &2050);
+        t.env.as_contract(&t.contract_id, || {
+            t.env.storage().instance().set(&LegacyDataKey::State, &State::Running);
+            if legacy_version == 0 {
+                let auction_data = AuctionDataV0 {
+                    host: legacy_data.buyer.clone(),
+                    token: legacy_data.token.clone(),
+                    counter_token: legacy_data.counter_token.clone(),
+                    auction_start_ledger: legacy_data.auction_start_ledger,
+                    bid_start_amount: legacy_data.bid_start_amount,
+                    bid_incr_amount: legacy_data.bid_incr_amount,
+                    bid_incr_interval: legacy_data.bid_incr_interval,
+                    bid_incr_times: legacy_data.bid_incr_times,
+                    bid_max_amount: legacy_data.bid_max_amount,
+                };
+                t.env.storage().instance().set(&LegacyDataKey::AuctionData, &auction_data);
+            } else {
+                t.env.storage().instance().set(&LegacyDataKey::Data, &legacy_data);
+            }
+        });
+        t.env.ledger().with_mut(|li|li.sequence_number = 35);
+        assert_eq!(auction_client.initialize(&t.admin, &t.salt), Status::AdminSet);
+        assert_eq!(auction_client.get_storage_version(), legacy_version);
+        assert_eq!(auction_client.try_get_auction_data(), Err(Ok(Error::NotInitialised)));
+
+        // Nothing but an upgrade and the migration can be called until the storage has been migrated
+        assert_eq!(auction_client.try_sell_token(&t.seller, &0, &None), Err(Ok(Error::NotMigrated)));
+        assert_eq!(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &2050), Err(Ok(Error::NotMigrated)));
+
+        // Only the admin can upgrade and migrate. The test contract keeps its code, the upgrade points it at the same Wasm
+        let wasm_hash = t.env.crypto().sha256(&Bytes::new(&t.env)).to_bytes();
+        assert_eq!(auction_client.upgrade(&wasm_hash), Status::Upgraded);
+        assert_eq!(t.env.auths()[0].0, t.admin);
+        assert_eq!(auction_client.migrate(), Status::Migrated);
+        assert_eq!(t.env.auths()[0].0, t.admin);
+        assert_eq!(auction_client.get_storage_version(), 2);
+        assert_eq!(auction_client.migrate(), Status::UpToDate);
+
+        // The running auction continues where it was, with the escrow it held. The tokens sent by accident can be rescued
+        let auction_data = auction_client.get_auction_data();
+        assert_eq!((auction_data.buyer, auction_data.bid_max_amount, auction_data.cancel_notice), (t.buyer.clone(), 2000, 0));
+        let summary = auction_client.get_auction_summary();
+        assert_eq!((summary.id, summary.state, summary.balances), (1, State::Running, Balances { counter_token: 2000, token: 0 }));
+        assert_eq!(auction_client.rescue_tokens(&t.counter_token.address, &t.admin, &50), Status::Rescued);
+        assert_eq!(auction_client.get_bid_info().current_bid, 1300);
+        assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+        assert_eq!(t.counter_token.balance(&t.seller), 1300);
+        assert_eq!(auction_client.close_auction(), Status::Closed);
+        assert_eq!(t.counter_token.balance(&t.buyer), 100_000 + 700);
+        assert_eq!(t.token.balance(&t.buyer), 1);
+        t.env.as_contract(&t.contract_id, || assert!(!t.env.storage().instance().has(&LegacyDataKey::State)));
+    }
+}
+
+// The returns of every entrypoint are covered below, except the parameter validation of setup_auction and amend_auction,
+// which is covered by test_setup_validation, and the migration itself, covered by test_upgrade_and_migrate
+#[test]
+fn test_admin_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let wasm_hash = t.env.crypto().sha256(&Bytes::new(&t.env)).to_bytes();
+
+    assert_eq!(outcome(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &1)), Err(Error::NotInitialised));
+    assert_eq!(outcome(auction_client.try_upgrade(&wasm_hash)), Err(Error::NotInitialised));
+    assert_eq!(outcome(auction_client.try_migrate()), Err(Error::NotInitialised));
+
+
+    // Only the deployer of the contract can become its admin, with the salt it was deployed with
+    assert_eq!(outcome(auction_client.try_initialize(&t.buyer, &t.salt)), Err(Error::NotDeployer));
+    let other_salt = soroban_sdk::BytesN::from_array(&t.env, &[2; 32]);
+    assert_eq!(outcome(auction_client.try_initialize(&t.admin, &other_salt)), Err(Error::NotDeployer));
+    assert_eq!(outcome(auction_client.try_initialize(&t.admin, &t.salt)), Ok(Status::AdminSet));
+    assert_eq!(outcome(auction_client.try_initialize(&t.buyer, &t.salt)), Err(Error::AlreadyIntitialised));
+    assert_eq!(outcome(auction_client.try_migrate()), Ok(Status::UpToDate));
+
+    // Only the 300 donated on top of the escrow can be rescued
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    t.counter_token.mint(&t.contract_id, &300);
+    assert_eq!(outcome(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &0)), Ok(Status::ExceedsUntracked));
+    assert_eq!(outcome(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &301)), Ok(Status::ExceedsUntracked));
+    t.counter_token.set_failing(&true);
+    assert_eq!(outcome(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &300)), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    assert_eq!(outcome(auction_client.try_rescue_tokens(&t.counter_token.address, &t.admin, &300)), Ok(Status::Rescued));
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+    assert_eq!(t.counter_token.balance(&t.admin), 300);
+
+    // The contract can only be upgraded once the auction has paid out its escrow
+    assert_eq!(outcome(auction_client.try_upgrade(&wasm_hash)), Ok(Status::EscrowHeld));
+    assert_eq!(outcome(auction_client.try_close_auction()), Ok(Status::Aborted));
+    assert_eq!(outcome(auction_client.try_upgrade(&wasm_hash)), Ok(Status::Upgraded));
+}
+
+#[test]
+fn test_setup_auction_returns(){
+    let t = setup_test();
+
+    // A failed or short escrow transfer rolls the whole setup back
+    t.counter_token.set_failing(&true);
+    assert_eq!(t.setup(false, None, 0), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    t.counter_token.set_fee(&1);
+    assert_eq!(t.setup(false, None, 0), Err(Error::TransferAmountMismatch));
+    t.counter_token.set_fee(&0);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+    assert_eq!(t.auction_client.try_get_state(), Err(Ok(Error::NotInitialised)));
+
+    assert_eq!(t.setup(false, Some(t.env.ledger().sequence() + 10), 0), Ok(Status::Scheduled));
+    assert_eq!(t.setup(false, None, 0), Ok(Status::AlreadyInitialised));
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+}
+
+#[test]
+fn test_sell_token_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let sell = || outcome(auction_client.try_sell_token(&t.seller, &0, &None));
+
+    assert_eq!(sell(), Ok(Status::NotInitialised));
+    assert_eq!(t.setup(false, Some(t.env.ledger().sequence() + 10), 0), Ok(Status::Scheduled));
+    assert_eq!(sell(), Ok(Status::NotStarted));
+    t.advance(10);
+    assert_eq!(auction_client.pause_auction(&t.buyer), Status::Paused);
+    assert_eq!(sell(), Ok(Status::Paused));
+    assert_eq!(auction_client.resume_auction(&t.buyer), Status::Resumed);
+
+    // Failed transfers of either token, a token that arrives short, all roll the sale back
+    t.token.set_failing(&true);
+    assert_eq!(sell(), Err(Error::TransferFailed));
+    t.token.set_failing(&false);
+    t.token.set_fee(&1);
+    assert_eq!(sell(), Err(Error::TransferAmountMismatch));
+    t.token.set_fee(&0);
+    t.counter_token.set_failing(&true);
+    assert_eq!(sell(), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    assert_eq!(auction_client.get_state(), State::Running);
+    assert_eq!(t.token.balance(&t.seller), 1);
+
+    // The bid of 1000 must reach the seller's minimum price, up to and including its last ledger
+    let sell_at_least = |min_price: i128, valid_until_ledger: Option<u32>| {
+        outcome(auction_client.try_sell_token(&t.seller, &min_price, &valid_until_ledger))
+    };
+    let ledger = t.env.ledger().sequence();
+    assert_eq!(sell_at_least(1001, None), Err(Error::PriceBelowMinimum));
+    assert_eq!(sell_at_least(1000, Some(ledger - 1)), Err(Error::SaleExpired));
+    assert_eq!(sell_at_least(1000, Some(ledger)), Ok(Status::Fulfilled));
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+    assert_eq!(sell(), Ok(Status::NotRunning));
+
+    // With an arbiter, the payment is held until the dispute window has passed
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+    t.token.mint(&t.seller, &1);
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+    assert_eq!(sell(), Ok(Status::PendingRelease));
+    assert_eq!(sell(), Ok(Status::NotRunning));
+}
+
+#[test]
+fn test_settle_signed_order_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    t.advance(5);
+    let ledger = t.env.ledger().sequence();
+    let order = |auction_id: u32, min_price: i128, valid_until_ledger: u32| SellOrder {
+        auction_id, seller: t.seller.clone(), min_price, valid_until_ledger,
+    };
+    let settle = |order: SellOrder| outcome(auction_client.try_settle_signed_order(&order));
+
+    assert_eq!(settle(order(1, 0, ledger)), Ok(Status::NotInitialised));
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // The order holds the seller to the auction it was signed for, its minimum price and its last ledger
+    assert_eq!(settle(order(2, 0, ledger)), Err(Error::WrongAuction));
+    assert_eq!(settle(order(1, 1001, ledger)), Err(Error::PriceBelowMinimum));
+    assert_eq!(settle(order(1, 1000, ledger - 1)), Err(Error::SaleExpired));
+    assert_eq!(auction_client.get_state(), State::Running);
+
+    assert_eq!(settle(order(1, 1000, ledger)), Ok(Status::Fulfilled));
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+    assert_eq!(settle(order(1, 1000, ledger)), Ok(Status::NotRunning));
+}
+
+#[test]
+fn test_pause_resume_amend_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let pause = || outcome(auction_client.try_pause_auction(&t.buyer));
+    let resume = || outcome(auction_client.try_resume_auction(&t.buyer));
+
+    assert_eq!(pause(), Ok(Status::NotInitialised));
+    assert_eq!(resume(), Ok(Status::NotInitialised));
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1000, &100, &0)), Ok(Status::NotInitialised));
+
+    assert_eq!(t.setup(false, Some(t.env.ledger().sequence() + 10), 0), Ok(Status::Scheduled));
+    assert_eq!(pause(), Ok(Status::NotStarted));
+    assert_eq!(resume(), Ok(Status::NotPaused));
+    t.advance(10);
+
+    // Raising the maximum bid to 2500 tops up the escrow, which fails with the transfer
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &999, &100, &0)), Ok(Status::AmendmentLowersPrice));
+    t.counter_token.set_failing(&true);
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1500, &100, &0)), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    t.counter_token.set_fee(&1);
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1500, &100, &0)), Err(Error::TransferAmountMismatch));
+    t.counter_token.set_fee(&0);
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2000);
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1500, &100, &0)), Ok(Status::Amended));
+    assert_eq!(t.counter_token.balance(&t.contract_id), 2500);
+
+    assert_eq!(pause(), Ok(Status::Paused));
+    assert_eq!(pause(), Ok(Status::NotRunning));
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1500, &100, &0)), Ok(Status::NotRunning));
+    assert_eq!(resume(), Ok(Status::Resumed));
+    assert_eq!(resume(), Ok(Status::NotPaused));
+}
+
+#[test]
+fn test_operator_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let operator = Address::generate(&t.env);
+    let set_operator = |operator: Option<Address>| outcome(auction_client.try_set_operator(&operator));
+
+    assert_eq!(set_operator(Some(operator.clone())), Ok(Status::NotInitialised));
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // Nobody but the buyer manages the auction, until the buyer appoints an operator
+    assert_eq!(outcome(auction_client.try_pause_auction(&operator)), Ok(Status::NotManager));
+    assert_eq!(outcome(auction_client.try_amend_auction(&operator, &1000, &100, &0)), Ok(Status::NotManager));
+    assert_eq!(set_operator(Some(operator.clone())), Ok(Status::OperatorSet));
+
+    // The operator pauses, resumes and amends the auction within the escrow the buyer funded, and the buyer can still manage it too.
+    // Only the buyer raises the maximum bid past its escrow, so the operator never moves the buyer's funds
+    assert_eq!(outcome(auction_client.try_pause_auction(&operator)), Ok(Status::Paused));
+    assert_eq!(outcome(auction_client.try_resume_auction(&operator)), Ok(Status::Resumed));
+    t.env.ledger().with_mut(|li|li.sequence_number += 50);
+    assert_eq!(outcome(auction_client.try_amend_auction(&operator, &1500, &100, &0)), Ok(Status::Amended));
+    assert_eq!(outcome(auction_client.try_amend_auction(&operator, &1501, &100, &0)), Ok(Status::ExceedsEscrow));
+    assert_eq!(outcome(auction_client.try_amend_auction(&operator, &1500, &100, &1)), Ok(Status::ExceedsEscrow));
+    assert_eq!(auction_client.get_auction_data().bid_max_amount, 2000);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 2000);
+    assert_eq!(outcome(auction_client.try_amend_auction(&t.buyer, &1500, &100, &1)), Ok(Status::Amended));
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000 - 2100);
+    assert_eq!(t.counter_token.balance(&operator), 0);
+    assert_eq!(outcome(auction_client.try_pause_auction(&t.buyer)), Ok(Status::Paused));
+
+    // Once removed, the operator no longer manages the auction, and the removal names it
+    assert_eq!(set_operator(None), Ok(Status::OperatorSet));
+    let (_, _, data) = t.env.events().all().last().unwrap();
+    assert_eq!(OperatorEvent::try_from_val(&t.env, &data).unwrap(), OperatorEvent { version: EVENT_VERSION, operator: operator.clone(), appointed: false });
+    assert_eq!(outcome(auction_client.try_resume_auction(&operator)), Ok(Status::NotManager));
+
+    // The operator can not outlive the auction
+    assert_eq!(set_operator(Some(operator.clone())), Ok(Status::OperatorSet));
+    assert_eq!(auction_client.close_auction(), Status::Aborted);
+    assert_eq!(set_operator(None), Ok(Status::AlreadyClosed));
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+    t.env.as_contract(&t.contract_id, || assert!(!t.env.storage().persistent().has(&AuctionKey::Operator(1))));
+    assert_eq!(set_operator(None), Ok(Status::NotInitialised));
+}
+
+#[test]
+fn test_escrow_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let raise = || outcome(auction_client.try_raise_dispute());
+    let resolve = |release: bool| outcome(auction_client.try_resolve_dispute(&release));
+    let finalize = || outcome(auction_client.try_finalize());
+
+    assert_eq!(raise(), Ok(Status::NotInitialised));
+    assert_eq!(resolve(true), Ok(Status::NotInitialised));
+    assert_eq!(finalize(), Ok(Status::NotInitialised));
+
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+    assert_eq!(raise(), Ok(Status::NotPendingRelease));
+    assert_eq!(resolve(true), Ok(Status::NotDisputed));
+    assert_eq!(finalize(), Ok(Status::NotPendingRelease));
+
+    // A disputed sale is refunded, once the tokens can be transferred
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    assert_eq!(finalize(), Ok(Status::DisputeWindowOpen));
+    assert_eq!(outcome(auction_client.try_close_auction()), Err(Error::NotRunning));
+    assert_eq!(raise(), Ok(Status::DisputeRaised));
+    assert_eq!(raise(), Ok(Status::NotPendingRelease));
+    t.counter_token.set_failing(&true);
+    assert_eq!(resolve(true), Err(Error::TransferFailed));
+    assert_eq!(resolve(false), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    assert_eq!(resolve(false), Ok(Status::Refunded));
+    assert_eq!(t.token.balance(&t.seller), 1);
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+
+    // An undisputed sale is released after the dispute window
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+    assert_eq!(t.setup(true, None, 0), Ok(Status::Started));
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::PendingRelease);
+    t.advance(50);
+    assert_eq!(raise(), Ok(Status::DisputeWindowClosed));
+    t.counter_token.set_failing(&true);
+    assert_eq!(finalize(), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    assert_eq!(finalize(), Ok(Status::Released));
+    assert_eq!(finalize(), Ok(Status::NotPendingRelease));
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+}
+
+#[test]
+fn test_close_reset_bump_returns(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    let close = || outcome(auction_client.try_close_auction());
+    let reset = || outcome(auction_client.try_reset_auction());
+
+    assert_eq!(close(), Ok(Status::NotInitialised));
+    assert_eq!(reset(), Err(Error::NotInitialised));
+    assert_eq!(outcome(auction_client.try_bump()), Ok(Status::Bumped));
+
+    // With a notice, the refund waits 20 ledgers and fails if the counter token can not be transferred
+    assert_eq!(t.setup(false, None, 20), Ok(Status::Started));
+    assert_eq!(reset(), Err(Error::NotYetClosed));
+    assert_eq!(close(), Ok(Status::Cancelling));
+    assert_eq!(close(), Ok(Status::NoticePeriod));
+    t.advance(20);
+    t.counter_token.set_failing(&true);
+    assert_eq!(close(), Err(Error::TransferFailed));
+    t.counter_token.set_failing(&false);
+    assert_eq!(close(), Ok(Status::Aborted));
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+    assert_eq!(close(), Ok(Status::AlreadyClosed));
+    assert_eq!(reset(), Ok(Status::Reset));
+    assert_eq!(reset(), Err(Error::NotInitialised));
+
+    // Without a notice, a running auction is aborted at once
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(close(), Ok(Status::Aborted));
+    assert_eq!(reset(), Ok(Status::Reset));
+
+    // Closing a sold auction fails if the bought token can not be transferred
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+    assert_eq!(auction_client.sell_token(&t.seller, &0, &None), Status::Fulfilled);
+    t.token.set_failing(&true);
+    assert_eq!(close(), Err(Error::TransferFailed));
+    t.token.set_failing(&false);
+    assert_eq!(close(), Ok(Status::Closed));
+    assert_eq!(t.token.balance(&t.buyer), 1);
+    assert_eq!(t.counter_token.balance(&t.buyer), 99_000);
+    assert_eq!(reset(), Ok(Status::Reset));
+}
+
+#[test]
+fn test_authorisation(){
+    // Only the given authorisations are mocked, instead of every call being authorised.
+    // A failed authorisation aborts the test process, as the panic can not unwind through the host,
+    // so each call is made with only its own party's authorisation, and checked to require exactly that
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+
+    // Make the call authorised by `party` only, for `auth_args` and the transfer it authorises along with it, if any
+    let call = |party: &Address, fn_name: &str, args: soroban_sdk::Vec<Val>, auth_args: soroban_sdk::Vec<Val>, transfer: Option<(&Address, i128)>| -> Status {
+        let transfer_args: Option<soroban_sdk::Vec<Val>> = transfer.map(|(_, amount)| (party.clone(), t.contract_id.clone(), amount).into_val(&t.env));
+        let sub_invokes: std::vec::Vec<MockAuthInvoke> = transfer.iter().zip(transfer_args.iter()).map(|((token, _), args)| MockAuthInvoke {
+            contract: token, fn_name: "transfer", args: args.clone(), sub_invokes: &[],
+        }).collect();
+        t.env.mock_auths(&[MockAuth {
+            address: party,
+            invoke: &MockAuthInvoke { contract: &t.contract_id, fn_name, args: auth_args.clone(), sub_invokes: &sub_invokes },
+        }]);
+        let status = t.env.invoke_contract::<Status>(&t.contract_id, &Symbol::new(&t.env, fn_name), args);
+
+        let auths = t.env.auths();
+        assert_eq!(auths.len(), 1, "{fn_name} requires more than one authorisation");
+        assert_eq!(auths[0].0, *party);
+        assert_eq!(auths[0].1.function, AuthorizedFunction::Contract((t.contract_id.clone(), Symbol::new(&t.env, fn_name), auth_args)));
+        assert_eq!(auths[0].1.sub_invocations.len(), sub_invokes.len());
+        status
+    };
+    // Calls authorised by the party with the arguments of the call, and calls on the auction with the given id
+    let with_args = |party: &Address, fn_name: &str, args: soroban_sdk::Vec<Val>, transfer: Option<(&Address, i128)>| {
+        call(party, fn_name, args.clone(), args, transfer)
+    };
+    let on_auction = |party: &Address, fn_name: &str, id: u32| call(party, fn_name, vec![&t.env], vec![&t.env, id.into_val(&t.env)], None);
+
+    // The admin authorises its calls with their arguments
+    assert_eq!(with_args(&t.admin, "initialize", vec![&t.env, t.admin.into_val(&t.env), t.salt.into_val(&t.env)], None), Status::AdminSet);
+    let wasm_hash = t.env.crypto().sha256(&Bytes::new(&t.env)).to_bytes();
+    assert_eq!(with_args(&t.admin, "upgrade", vec![&t.env, wasm_hash.into_val(&t.env)], None), Status::Upgraded);
+    assert_eq!(with_args(&t.admin, "migrate", vec![&t.env], None), Status::UpToDate);
+
+    // The buyer authorises the setup and its escrow with the arguments of the setup
+    let escrow = Some(EscrowTerms { arbiter: t.arbiter.clone(), dispute_window: 50 });
+    let setup_args = (t.buyer.clone(), t.token.address.clone(), t.counter_token.address.clone(), 1000_i128, 100_i128, 10_u32, 10_u32, escrow, None::<u32>, 0_u32);
+    assert_eq!(with_args(&t.buyer, "setup_auction", setup_args.into_val(&t.env), Some((&t.counter_token.address, 2000))), Status::Started);
+
+    // Calls on the running auction are authorised for its id, so they can not be used on a later auction
+    let managed = |party: &Address, fn_name: &str| call(party, fn_name, vec![&t.env, party.into_val(&t.env)], vec![&t.env, 1_u32.into_val(&t.env)], None);
+    assert_eq!(managed(&t.buyer, "pause_auction"), Status::Paused);
+    assert_eq!(managed(&t.buyer, "resume_auction"), Status::Resumed);
+    let amend_args = (t.buyer.clone(), 1000_i128, 110_i128, 0_u32).into_val(&t.env);
+    let amend_auth_args: soroban_sdk::Vec<Val> = (1_u32, 1000_i128, 110_i128, 0_u32).into_val(&t.env);
+    assert_eq!(call(&t.buyer, "amend_auction", amend_args, amend_auth_args.clone(), Some((&t.counter_token.address, 100))), Status::Amended);
+
+    // The operator authorises its own calls, and only the buyer appoints it
+    let operator = Address::generate(&t.env);
+    let operator_args = vec![&t.env, Some(operator.clone()).into_val(&t.env)];
+    assert_eq!(call(&t.buyer, "set_operator", operator_args, (1_u32, Some(operator.clone())).into_val(&t.env), None), Status::OperatorSet);
+    assert_eq!(managed(&operator, "pause_auction"), Status::Paused);
+    assert_eq!(managed(&operator, "resume_auction"), Status::Resumed);
+    let amend_args = (operator.clone(), 1000_i128, 110_i128, 0_u32).into_val(&t.env);
+    assert_eq!(call(&operator, "amend_auction", amend_args, amend_auth_args, None), Status::Amended);
+
+    // Only the buyer raises the maximum bid past the escrow it funded, authorising the top-up along with its amendment
+    let amend_auth_args: soroban_sdk::Vec<Val> = (1_u32, 1100_i128, 110_i128, 0_u32).into_val(&t.env);
+    let amend_args = (operator.clone(), 1100_i128, 110_i128, 0_u32).into_val(&t.env);
+    assert_eq!(call(&operator, "amend_auction", amend_args, amend_auth_args.clone(), None), Status::ExceedsEscrow);
+    let amend_args = (t.buyer.clone(), 1100_i128, 110_i128, 0_u32).into_val(&t.env);
+    assert_eq!(call(&t.buyer, "amend_auction", amend_args, amend_auth_args, Some((&t.counter_token.address, 100))), Status::Amended);
+    let sell_args = (t.seller.clone(), 1100_i128, Some(100_u32)).into_val(&t.env);
+    let sell_auth_args = (1_u32, 1100_i128, Some(100_u32)).into_val(&t.env);
+    assert_eq!(call(&t.seller, "sell_token", sell_args, sell_auth_args, Some((&t.token.address, 1))), Status::PendingRelease);
+    assert_eq!(on_auction(&t.buyer, "raise_dispute", 1), Status::DisputeRaised);
+    let resolve_args = vec![&t.env, false.into_val(&t.env)];
+    assert_eq!(call(&t.arbiter, "resolve_dispute", resolve_args, (1_u32, false).into_val(&t.env), None), Status::Refunded);
+    assert_eq!(on_auction(&t.buyer, "reset_auction", 1), Status::Reset);
+
+    // Only the admin rescues tokens
+    t.counter_token.mint(&t.contract_id, &1);
+    let rescue_args = (t.counter_token.address.clone(), t.admin.clone(), 1_i128).into_val(&t.env);
+    assert_eq!(with_args(&t.admin, "rescue_tokens", rescue_args, None), Status::Rescued);
+
+    let setup_args = (t.buyer.clone(), t.token.address.clone(), t.counter_token.address.clone(), 1000_i128, 100_i128, 10_u32, 10_u32, None::<EscrowTerms>, None::<u32>, 0_u32);
+    assert_eq!(with_args(&t.buyer, "setup_auction", setup_args.into_val(&t.env), Some((&t.counter_token.address, 2000))), Status::Started);
+    assert_eq!(on_auction(&t.buyer, "close_auction", 2), Status::Aborted);
+
+    // Anyone can bump, finalize or read the auction, without any authorisation
+    t.env.mock_auths(&[]);
+    assert_eq!(auction_client.bump(), Status::Bumped);
+    assert_eq!(auction_client.finalize(), Status::NotPendingRelease);
+    assert_eq!(auction_client.get_state(), State::Aborted);
+    assert_eq!(auction_client.get_auction_summary().id, 2);
+    assert_eq!(auction_client.get_bid_info_at(&0).start_ledger, 0);
+    assert_eq!(auction_client.get_history(&1, &10).len(), 1);
+    assert_eq!(auction_client.get_storage_version(), 2);
+    assert!(t.env.auths().is_empty());
+    assert_eq!(t.counter_token.balance(&t.buyer), 100_000);
+}
+
+#[test]
+fn test_signed_order_authorisation(){
+    let t = setup_test();
+    let auction_client = &t.auction_client;
+    assert_eq!(t.setup(false, None, 0), Ok(Status::Started));
+
+    // A relayer settles an order the seller signed, which is authorised by the seller only, with the order itself
+    let order = SellOrder { auction_id: 1, seller: t.seller.clone(), min_price: 1000, valid_until_ledger: 100 };
+    let order_args: soroban_sdk::Vec<Val> = vec![&t.env, order.clone().into_val(&t.env)];
+    let transfer_args: soroban_sdk::Vec<Val> = (t.seller.clone(), t.contract_id.clone(), 1_i128).into_val(&t.env);
+    t.env.mock_auths(&[MockAuth {
+        address: &t.seller,
+        invoke: &MockAuthInvoke {
+            contract: &t.contract_id, fn_name: "settle_signed_order", args: order_args.clone(),
+            sub_invokes: &[MockAuthInvoke { contract: &t.token.address, fn_name: "transfer", args: transfer_args.clone(), sub_invokes: &[] }],
+        },
+    }]);
+    assert_eq!(auction_client.settle_signed_order(&order), Status::Fulfilled);
+    assert_eq!(t.env.auths(), std::vec![(t.seller.clone(), soroban_sdk::testutils::AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((t.contract_id.clone(), Symbol::new(&t.env, "settle_signed_order"), order_args)),
+        sub_invocations: std::vec![soroban_sdk::testutils::AuthorizedInvocation {
+            function: AuthorizedFunction::Contract((t.token.address.clone(), Symbol::new(&t.env, "transfer"), transfer_args)),
+            sub_invocations: std::vec![],
+        }],
+    })]);
+    assert_eq!(t.counter_token.balance(&t.seller), 1000);
+}
+
+// The invocation of `fn_name` of `contract` with `args`, as a contract account authorises it, along with the calls it makes
+fn authorized_invocation(env: &Env, (contract, fn_name, args): (&Address, &str, soroban_sdk::Vec<Val>),
+                         sub_invocations: std::vec::Vec<SorobanAuthorizedInvocation>) -> SorobanAuthorizedInvocation {
+    let args: std::vec::Vec<ScVal> = args.iter().map(|arg| ScVal::try_from_val(env, &arg).unwrap()).collect();
+    SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+            contract_address: ScAddress::try_from(contract).unwrap(),
+            function_name: fn_name.try_into().unwrap(),
+            args: args.try_into().unwrap(),
+        }),
+        sub_invocations: sub_invocations.try_into().unwrap(),
+    }
+}
+
+// Set the signed authorisation of `account` for `invocation`, and return the payload the account is asked to check
+fn authorise_account(env: &Env, account: &Address, nonce: i64, invocation: SorobanAuthorizedInvocation) -> soroban_sdk::BytesN<32> {
+    let signature_expiration_ledger = env.ledger().sequence() + 100;
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(env.ledger().network_id().to_array()),
+        nonce,
+        signature_expiration_ledger,
+        invocation: invocation.clone(),
+    });
+    env.set_auths(&[SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: ScAddress::try_from(account).unwrap(),
+            nonce,
+            signature_expiration_ledger,
+            signature: ScVal::Void,
+        }),
+        root_invocation: invocation,
+    }]);
+    env.crypto().sha256(&Bytes::from_slice(env, &preimage.to_xdr(Limits::none()).unwrap())).to_bytes()
+}
+
+#[test]
+fn test_contract_account_buyer(){
+    // A buyer that is a contract account, like a multisig wallet, checks every authorisation in its own __check_auth.
+    // Its calls are made with signed authorisations, as they are on the network, instead of mocked ones
+    let env = Env::default();
+    let buyer = env.register_contract(None, MockAccount);
+    let seller = Address::generate(&env);
+    let token = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+    token.mint(&seller, &1);
+    let counter_token = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+    counter_token.mint(&buyer, &100_000);
+    let contract_id = env.register_contract(None, AuctionContract);
+    let auction_client = AuctionContractClient::new(&env, &contract_id);
+    let account = MockAccountClient::new(&env, &buyer);
+
+    // The buyer authorises the setup, and the transfer of the escrow along with it
+    let setup_args = (buyer.clone(), token.address.clone(), counter_token.address.clone(), 1000_i128, 100_i128, 10_u32, 10_u32,
+                      None::<EscrowTerms>, None::<u32>, 0_u32).into_val(&env);
+    let escrow = authorized_invocation(&env, (&counter_token.address, "transfer", (buyer.clone(), contract_id.clone(), 2000_i128).into_val(&env)), std::vec![]);
+    let payload = authorise_account(&env, &buyer, 1, authorized_invocation(&env, (&contract_id, "setup_auction", setup_args), std::vec![escrow]));
+    assert_eq!(auction_client.setup_auction(&buyer, &token.address, &counter_token.address, &1000, &100, &10, &10, &None, &None, &0), Status::Started);
+    assert_eq!(account.last_payload(), Some(payload));
+    assert_eq!(counter_token.balance(&buyer), 98_000);
+
+    // The wallet appoints an operator for the day-to-day management
+    let operator = Address::generate(&env);
+    let operator_args = (1_u32, Some(operator.clone())).into_val(&env);
+    let payload = authorise_account(&env, &buyer, 2, authorized_invocation(&env, (&contract_id, "set_operator", operator_args), std::vec![]));
+    assert_eq!(auction_client.set_operator(&Some(operator.clone())), Status::OperatorSet);
+    assert_eq!(account.last_payload(), Some(payload));
+
+    env.mock_all_auths();
+    assert_eq!(auction_client.pause_auction(&operator), Status::Paused);
+    assert_eq!(auction_client.resume_auction(&operator), Status::Resumed);
+    assert_eq!(auction_client.sell_token(&seller, &1000, &None), Status::Fulfilled);
+
+    // Only the wallet itself withdraws the token and the remaining escrow
+    let payload = authorise_account(&env, &buyer, 3, authorized_invocation(&env, (&contract_id, "close_auction", (1_u32,).into_val(&env)), std::vec![]));
+    assert_eq!(auction_client.close_auction(), Status::Closed);
+    assert_eq!(account.last_payload(), Some(payload));
+    assert_eq!((token.balance(&buyer), counter_token.balance(&buyer)), (1, 99_000));
+
+    let payload = authorise_account(&env, &buyer, 4, authorized_invocation(&env, (&contract_id, "reset_auction", (1_u32,).into_val(&env)), std::vec![]));
+    assert_eq!(auction_client.reset_auction(), Status::Reset);
+    assert_eq!(account.last_payload(), Some(payload));
+}
+
+// Run an unauthorised call of test_unauthorised_calls, in the child process it starts for it
+fn unauthorised_call(case: &str) {
+    let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+    env.mock_all_auths();
+    let buyer = Address::generate(&env);
+    let counter_asset = create_token_contract(&env, &Address::generate(&env));
+    counter_asset.mint(&buyer, &100_000);
+    let contract_id = env.register_contract(None, AuctionContract);
+    let auction_client = AuctionContractClient::new(&env, &contract_id);
+    auction_client.setup_auction(&buyer, &Address::generate(&env), &counter_asset.address, &1000, &100, &10, &10, &None, &None, &0);
+
+    let [fn_name, party, id]: [&str; 3] = case.split(' ').collect::<std::vec::Vec<_>>().try_into().unwrap();
+    let id: u32 = id.parse().unwrap();
+    let party = match party {
+        "buyer" => buyer,
+        "operator" => {
+            let operator = Address::generate(&env);
+            auction_client.set_operator(&Some(operator.clone()));
+            operator
+        }
+        _ => Address::generate(&env),
+    };
+    if fn_name == "reset_auction" {
+        assert_eq!(auction_client.close_auction(), Status::Aborted);
+    }
+    env.mock_auths(&[MockAuth {
+        address: &party,
+        invoke: &MockAuthInvoke { contract: &contract_id, fn_name, args: vec![&env, id.into_val(&env)], sub_invokes: &[] },
+    }]);
+    env.invoke_contract::<Status>(&contract_id, &Symbol::new(&env, fn_name), vec![&env]);
+}
+
+#[test]
+fn test_unauthorised_calls(){
+    // A failed authorisation aborts the process, so each call runs in a child process of this test binary.
+    // Only the buyer's call on the current auction succeeds. Calls by anyone else, the operator included, or authorised for another auction, abort
+    if let Ok(case) = std::env::var("UNAUTHORISED_CALL") {
+        return unauthorised_call(&case);
+    }
+    for fn_name in ["close_auction", "reset_auction"] {
+        for (party, id) in [("buyer", 1), ("seller", 1), ("operator", 1), ("buyer", 2)] {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", "test::test_unauthorised_calls", "--test-threads=1"])
+                .env("UNAUTHORISED_CALL", std::format!("{fn_name} {party} {id}"))
+                .output()
+                .unwrap();
+            assert_eq!(output.status.success(), (party, id) == ("buyer", 1), "{fn_name} by the {party} for auction {id}");
+        }
+    }
+}
+
+// Resources used by a call in the test environment, on the Wasm of the contract as it is deployed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Cost {
+    cpu_insns: u64,
+    mem_bytes: u64,
+    read_entries: u64,
+    write_entries: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+impl Cost {
+    const NAMES: [&'static str; 6] = ["cpu_insns", "mem_bytes", "read_entries", "write_entries", "read_bytes", "write_bytes"];
+
+    fn values(&self) -> [u64; 6] {
+        [self.cpu_insns, self.mem_bytes, self.read_entries, self.write_entries, self.read_bytes, self.write_bytes]
+    }
+}
+
+// A call that uses more than this percentage more of any resource than the baseline fails test_budget_baseline
+const BUDGET_TOLERANCE: u64 = 5;
+
+// Build the release Wasm of the contract, so the budget includes the instructions of the Wasm VM. It is built for
+// wasm32v1-none, which only uses the Wasm features the Soroban VM supports, in its own target directory,
+// as the one of the tests is locked while they run
+fn contract_wasm() -> std::vec::Vec<u8> {
+    let target_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/budget");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let status = std::process::Command::new(cargo)
+        .args(["build", "--release", "--target", "wasm32v1-none", "-p", "soroban_auction", "--target-dir", target_dir])
+        .status()
+        .unwrap();
+    assert!(status.success(), "The Wasm of the contract could not be built, is the wasm32v1-none target installed?");
+    std::fs::read(std::format!("{target_dir}/wasm32v1-none/release/soroban_auction.wasm")).unwrap()
+}
+
+// Measure the budget and the ledger footprint of a call
+fn measure<T>(env: &Env, call: impl FnOnce() -> T) -> (T, Cost) {
+    env.budget().reset_default();
+    env.host().with_mut_storage(|storage| {
+        storage.footprint = Default::default();
+        Ok(())
+    }).unwrap();
+    let result = call();
+    let mut cost = Cost {
+        cpu_insns: env.budget().cpu_instruction_cost(),
+        mem_bytes: env.budget().memory_bytes_cost(),
+        ..Cost::default()
+    };
+
+    // Every entry in the footprint is read, and those with write access are written, at their size after the call
+    let budget = env.host().budget_cloned();
+    env.host().with_mut_storage(|storage| {
+        for (key, access) in storage.footprint.0.iter(&budget)? {
+            let size: u64 = match storage.map.get::<Rc<LedgerKey>>(key, &budget)? {
+                Some(Some((entry, _))) => entry.to_xdr(Limits::none()).unwrap().len() as u64,
+                _ => 0,
+            };
+            cost.read_entries += 1;
+            cost.read_bytes += size;
+            if *access == AccessType::ReadWrite {
+                cost.write_entries += 1;
+                cost.write_bytes += size;
+            }
+        }
+        Ok(())
+    }).unwrap();
+    (result, cost)
+}
+
+#[test]
+#[ignore = "builds the release Wasm, which needs the wasm32v1-none target; run with --ignored"]
+fn test_budget_baseline(){
+    // Set UPDATE_BASELINE=1 to rewrite the baseline after an intended change
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/budget_baseline.txt");
+    // The snapshot would hold the whole Wasm, and change along with any change to the contract
+    let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+    env.mock_all_auths();
+    env.ledger().with_mut(|li|li.sequence_number = 100);
+
+    let buyer = Address::generate(&env);
+    let seller = Address::generate(&env);
+    let asset = create_token_contract(&env, &Address::generate(&env));
+    asset.mint(&seller, &1);
+    let counter_asset = create_token_contract(&env, &Address::generate(&env));
+    counter_asset.mint(&buyer, &100_000);
+    let contract_id = env.register_contract_wasm(None, contract_wasm().as_slice());
+    let auction_client = AuctionContractClient::new(&env, &contract_id);
+
+    // An auction of a day at ~5 seconds per ledger, sold halfway and then closed
+    let (status, setup_cost) = measure(&env, || auction_client.setup_auction(&buyer, &asset.address, &counter_asset.address,
+                                                                             &1000, &10, &96, &180, &None, &None, &0));
+    assert_eq!(status, Status::Started);
+    env.ledger().with_mut(|li|li.sequence_number = 100 + 8640);
+    let (bid_info, bid_info_cost) = measure(&env, || auction_client.get_bid_info());
+    assert_eq!(bid_info.current_bid, 1480);
+    let (status, sell_cost) = measure(&env, || auction_client.sell_token(&seller, &0, &None));
+    assert_eq!(status, Status::Fulfilled);
+    let (status, close_cost) = measure(&env, || auction_client.close_auction());
+    assert_eq!(status, Status::Closed);
+
+    let measured = [("setup_auction", setup_cost), ("get_bid_info", bid_info_cost), ("sell_token", sell_cost), ("close_auction", close_cost)];
+    let mut report = std::format!("# entrypoint {}\n", Cost::NAMES.join(" "));
+    for (entrypoint, cost) in measured {
+        let values: std::vec::Vec<std::string::String> = cost.values().iter().map(|value| std::format!("{value}")).collect();
+        report += &std::format!("{entrypoint} {}\n", values.join(" "));
+    }
+    println!("{report}");
+    if std::env::var("UPDATE_BASELINE").is_ok() {
+        std::fs::write(path, &report).unwrap();
+    }
+
+    let baseline = std::fs::read_to_string(path).unwrap();
+    for (entrypoint, cost) in measured {
+        let line = baseline.lines()
+                           .find(|line| line.split_whitespace().next() == Some(entrypoint))
+                           .unwrap_or_else(|| panic!("No baseline for {entrypoint}, run with UPDATE_BASELINE=1"));
+        let expected: std::vec::Vec<u64> = line.split_whitespace().skip(1).map(|value| value.parse().unwrap()).collect();
+        for ((name, value), expected) in Cost::NAMES.iter().zip(cost.values()).zip(expected) {
+            assert!(value * 100 <= expected * (100 + BUDGET_TOLERANCE),
+                    "{entrypoint} uses {value} {name}, more than {BUDGET_TOLERANCE}% above the baseline of {expected}");
+        }
+    }
+}
+
+/// Auction parameters that pass validation, with a short schedule so the ledgers stay well within the TTL
+fn auction_params() -> impl Strategy<Value = AuctionParams> {
+    (1..1_000_000i128, 1..10_000i128, 1..20u32, 1..50u32, prop_oneof![Just(0u32), 1..100u32], prop_oneof![Just(0u32), 1..100u32])
+        .prop_map(|(start_bid, incr_amount, incr_times, incr_interval, start_delay, cancel_notice)| AuctionParams {
+            start_bid,
+            incr_amount,
+            incr_times,
+            incr_interval,
+            start_delay,
+            cancel_notice,
+        })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (1..200u32).prop_map(Op::Advance),
+        1 => Just(Op::Setup),
+        2 => Just(Op::Sell),
+        2 => Just(Op::Close),
+        1 => Just(Op::Reset),
+        1 => Just(Op::Pause),
+        1 => Just(Op::Resume),
+        1 => (0..1000i128, 0..100i128, 0..5u32).prop_map(|(bid_raise, incr_raise, extra_incr_times)| Op::Amend { bid_raise, incr_raise, extra_incr_times }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    // Random call sequences keep the tokens conserved, the escrow tracked, the bid monotonic and bounded,
+    // and Closed or Aborted auctions final until they are reset. The harness checks this after every call
+    #[test]
+    fn prop_call_sequences(params in auction_params(), ops in prop::collection::vec(op(), 1..40)){
+        let mut harness = Harness::new(params);
+        prop_assert!(matches!(harness.apply(Op::Setup), Some(Ok(Status::Started)) | Some(Ok(Status::Scheduled))));
+        for op in ops {
+            harness.apply(op);
+        }
+    }
+
+    #[test]
+    fn prop_sale_settles_escrow(params in auction_params(), sell_after in 0..1500u32, close_after in 0..200u32){
+        let mut harness = Harness::new(AuctionParams { cancel_notice: 0, ..params });
+        harness.apply(Op::Setup);
+        harness.apply(Op::Advance(sell_after));
+        let sold = harness.apply(Op::Sell) == Some(Ok(Status::Fulfilled));
+        prop_assert_eq!(sold, sell_after >= params.start_delay);
+        harness.apply(Op::Advance(close_after));
+        prop_assert_eq!(harness.apply(Op::Close), Some(Ok(if sold { Status::Closed } else { Status::Aborted })));
+
+        // The buyer paid exactly the sale price, and got the token
+        let counter_token = token::Client::new(&harness.env, &harness.counter_token);
+        let paid = Harness::BUYER_FUNDS - counter_token.balance(&harness.buyer);
+        let sale_price = harness.client().get_auction_summary().sale_price;
+        prop_assert_eq!(paid, sale_price.unwrap_or(0));
+        prop_assert_eq!(token::Client::new(&harness.env, &harness.token).balance(&harness.buyer), sold as i128);
+
+        // Nothing but a reset leaves the final state
+        let state = harness.state();
+        for op in [Op::Sell, Op::Pause, Op::Resume, Op::Close, Op::Setup, Op::Advance(10), Op::Close] {
+            harness.apply(op);
+            prop_assert_eq!(harness.state(), state);
+        }
+        prop_assert_eq!(harness.apply(Op::Reset), Some(Ok(Status::Reset)));
+        prop_assert_eq!(harness.state(), None);
+    }
 }
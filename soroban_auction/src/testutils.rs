@@ -0,0 +1,330 @@
+//! Support for testing the contract: a token that misbehaves on request, a contract account, and a harness that drives the contract
+//! through sequences of calls in the test environment, checking its invariants after every call.
+//! Used by the tests, the property tests and the fuzz target
+extern crate std;
+use std::vec::Vec;
+
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Ledger};
+use soroban_sdk::auth::{Context, CustomAccountInterface};
+use soroban_sdk::crypto::Hash;
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, BytesN, Env, Symbol, Val};
+
+use crate::types::{Error, State, Status};
+use crate::{AuctionContract, AuctionContractClient};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MockTokenError {
+    TransferRejected = 1,
+    InsufficientBalance = 2,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MockTokenKey {
+    Balance(Address),
+    Fee,
+    Failing,
+    Reentry,
+    Reentered,
+}
+
+/// A token whose transfers can be made to fail, to charge a fee, or to call back into the auction.
+/// It implements the part of the token interface the contract uses: `balance` and `transfer`.
+/// Its entries live for the network's maximum TTL, so only the TTL of the auction is under test
+#[contract]
+pub struct MockToken;
+
+#[contractimpl]
+impl MockToken {
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        let balance = Self::balance(env.clone(), to.clone());
+        Self::set_balance(&env, to, balance + amount);
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&MockTokenKey::Balance(id)).unwrap_or(0)
+    }
+
+    /// Burn `fee` of every transfer, so the receiver gets `amount - fee`
+    pub fn set_fee(env: Env, fee: i128) {
+        env.storage().instance().set(&MockTokenKey::Fee, &fee);
+    }
+
+    /// Reject every transfer while `failing` is set
+    pub fn set_failing(env: Env, failing: bool) {
+        env.storage().instance().set(&MockTokenKey::Failing, &failing);
+    }
+
+    /// Call `function` of `auction` with `args` during every transfer, after the balances have moved
+    pub fn set_reentry(env: Env, auction: Address, function: Symbol, args: soroban_sdk::Vec<Val>) {
+        env.storage().instance().set(&MockTokenKey::Reentry, &(auction, function, args));
+    }
+
+    /// Whether the last call back into the auction succeeded. The host rejects re-entry, so it never does
+    pub fn reentered(env: Env) -> bool {
+        env.storage().instance().get(&MockTokenKey::Reentered).unwrap_or(false)
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) -> Result<(), MockTokenError> {
+        from.require_auth();
+        if env.storage().instance().get(&MockTokenKey::Failing).unwrap_or(false) {
+            return Err(MockTokenError::TransferRejected)
+        }
+        let from_balance = Self::balance(env.clone(), from.clone());
+        if from_balance < amount {
+            return Err(MockTokenError::InsufficientBalance)
+        }
+        let fee: i128 = env.storage().instance().get(&MockTokenKey::Fee).unwrap_or(0);
+        Self::set_balance(&env, from.clone(), from_balance - amount);
+        let to_balance = Self::balance(env.clone(), to.clone());
+        Self::set_balance(&env, to, to_balance + amount - fee);
+
+        if let Some((auction, function, args)) = env.storage().instance().get::<_, (Address, Symbol, soroban_sdk::Vec<Val>)>(&MockTokenKey::Reentry) {
+            let reentry = env.try_invoke_contract::<Status, Error>(&auction, &function, args);
+            env.storage().instance().set(&MockTokenKey::Reentered, &reentry.is_ok());
+        }
+        Ok(())
+    }
+}
+
+impl MockToken {
+    fn set_balance(env: &Env, id: Address, balance: i128) {
+        let key = MockTokenKey::Balance(id);
+        let max_ttl: u32 = env.storage().max_ttl();
+        env.storage().persistent().set(&key, &balance);
+        env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+        env.storage().instance().extend_ttl(max_ttl, max_ttl);
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MockAccountKey {
+    Payload,
+}
+
+/// A contract account that accepts every authorisation, and keeps the payload it was last asked to check
+#[contract]
+pub struct MockAccount;
+
+#[contractimpl]
+impl MockAccount {
+    pub fn last_payload(env: Env) -> Option<BytesN<32>> {
+        env.storage().instance().get(&MockAccountKey::Payload)
+    }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for MockAccount {
+    type Signature = ();
+    type Error = Error;
+
+    #[allow(non_snake_case)]
+    fn __check_auth(env: Env, signature_payload: Hash<32>, _signatures: (), _auth_contexts: soroban_sdk::Vec<Context>) -> Result<(), Error> {
+        let payload: BytesN<32> = signature_payload.into();
+        env.storage().instance().set(&MockAccountKey::Payload, &payload);
+        Ok(())
+    }
+}
+
+/// Parameters of the auctions set up by the harness
+#[derive(Clone, Copy, Debug)]
+pub struct AuctionParams {
+    pub start_bid: i128,
+    pub incr_amount: i128,
+    pub incr_times: u32,
+    pub incr_interval: u32,
+    /// Ledgers between setting up the auction and its start
+    pub start_delay: u32,
+    pub cancel_notice: u32,
+}
+
+/// A call, or the passing of ledgers
+#[derive(Clone, Copy, Debug)]
+pub enum Op {
+    Advance(u32),
+    Setup,
+    Sell,
+    Close,
+    Reset,
+    Pause,
+    Resume,
+    /// Amend the auction, raising the current bid and bid increase by the given amounts
+    Amend { bid_raise: i128, incr_raise: i128, extra_incr_times: u32 },
+}
+
+/// The contract with two tokens, a buyer and the sellers that tried to sell
+pub struct Harness {
+    pub env: Env,
+    pub contract_id: Address,
+    pub buyer: Address,
+    pub token: Address,
+    pub counter_token: Address,
+    pub sellers: Vec<Address>,
+    pub params: AuctionParams,
+    /// Counter token minted to the buyer, and tokens minted to the sellers
+    minted: (i128, i128),
+    /// Id of the auction and the highest bid seen for it
+    highest_bid: Option<(u32, i128)>,
+}
+
+impl Harness {
+    /// Ledger at which the harness starts
+    pub const START_LEDGER: u32 = 100;
+
+    /// Counter token minted to the buyer, enough for many auctions and amendments
+    pub const BUYER_FUNDS: i128 = 1 << 100;
+
+    pub fn new(params: AuctionParams) -> Harness {
+        // Many calls run in one environment, so the budget is not limited, and no snapshot is written for every case
+        let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+        env.budget().reset_unlimited();
+        env.mock_all_auths();
+        // Entries live long enough that no sequence of operations lets the token balances be archived
+        env.ledger().with_mut(|li| {
+            li.sequence_number = Self::START_LEDGER;
+            li.min_persistent_entry_ttl = 1_000_000;
+            li.min_temp_entry_ttl = 1_000_000;
+            li.max_entry_ttl = 10_000_000;
+        });
+
+        let buyer = Address::generate(&env);
+        let token = env.register_stellar_asset_contract(Address::generate(&env));
+        let counter_token = env.register_stellar_asset_contract(Address::generate(&env));
+        token::StellarAssetClient::new(&env, &counter_token).mint(&buyer, &Self::BUYER_FUNDS);
+        let contract_id = env.register_contract(None, AuctionContract);
+
+        Harness {
+            env,
+            contract_id,
+            buyer,
+            token,
+            counter_token,
+            sellers: Vec::new(),
+            params,
+            minted: (Self::BUYER_FUNDS, 0),
+            highest_bid: None,
+        }
+    }
+
+    pub fn client(&self) -> AuctionContractClient<'_> {
+        AuctionContractClient::new(&self.env, &self.contract_id)
+    }
+
+    /// The state of the current auction, or None after a reset
+    pub fn state(&self) -> Option<State> {
+        self.client().try_get_state().ok().map(|state| state.unwrap())
+    }
+
+    /// Apply an operation and check the invariants. Returns the status of the call, if it was one
+    pub fn apply(&mut self, op: Op) -> Option<Result<Status, Error>> {
+        let state_before = self.state();
+        let client = AuctionContractClient::new(&self.env, &self.contract_id);
+        let result = match op {
+            Op::Advance(ledgers) => {
+                self.env.ledger().with_mut(|li| li.sequence_number += ledgers);
+                None
+            }
+            Op::Setup => {
+                let params = self.params;
+                let start_ledger = self.env.ledger().sequence() + params.start_delay;
+                Some(client.try_setup_auction(&self.buyer, &self.token, &self.counter_token, &params.start_bid,
+                                              &params.incr_amount, &params.incr_times, &params.incr_interval,
+                                              &None, &Some(start_ledger), &params.cancel_notice))
+            }
+            Op::Sell => {
+                let seller = Address::generate(&self.env);
+                token::StellarAssetClient::new(&self.env, &self.token).mint(&seller, &1);
+                self.minted.1 += 1;
+                self.sellers.push(seller.clone());
+
+                // The seller asks for the bid it sees, at this ledger only
+                let ledger = self.env.ledger().sequence();
+                let bid = client.try_get_bid_info_at(&ledger).ok().map(|bid_info| bid_info.unwrap().current_bid);
+                let result = client.try_sell_token(&seller, &bid.unwrap_or(0), &Some(ledger));
+                if let Ok(Ok(Status::Fulfilled)) = result {
+                    // The seller is paid exactly the bid at the ledger of the sale
+                    let paid = token::Client::new(&self.env, &self.counter_token).balance(&seller);
+                    assert_eq!(Some(paid), bid, "Seller not paid the current bid");
+                }
+                Some(result)
+            }
+            Op::Close => Some(client.try_close_auction()),
+            Op::Reset => Some(client.try_reset_auction()),
+            Op::Pause => Some(client.try_pause_auction(&self.buyer)),
+            Op::Resume => Some(client.try_resume_auction(&self.buyer)),
+            Op::Amend { bid_raise, incr_raise, extra_incr_times } => {
+                match client.try_get_bid_info_at(&self.env.ledger().sequence()) {
+                    Ok(Ok(bid_info)) => {
+                        let data = client.get_auction_data();
+                        Some(client.try_amend_auction(&self.buyer, &(bid_info.current_bid + bid_raise), &(data.bid_incr_amount + incr_raise),
+                                                      &extra_incr_times))
+                    }
+                    _ => None,
+                }
+            }
+        };
+        let result = result.map(|result| match result {
+            Ok(Ok(status)) => Ok(status),
+            Ok(Err(_)) => panic!("Status could not be converted"),
+            Err(Ok(error)) => Err(error),
+            Err(Err(_)) => panic!("Call failed without a contract error"),
+        });
+
+        self.check(op, state_before);
+        result
+    }
+
+    /// Check the invariants after an operation
+    fn check(&mut self, op: Op, state_before: Option<State>) {
+        let contract = self.contract_id.clone();
+        let token = token::Client::new(&self.env, &self.token);
+        let counter_token = token::Client::new(&self.env, &self.counter_token);
+
+        // Tokens are never created or destroyed by the contract, only moved between the buyer, the sellers and the contract
+        let mut counter_token_total: i128 = counter_token.balance(&self.buyer) + counter_token.balance(&contract);
+        let mut token_total: i128 = token.balance(&self.buyer) + token.balance(&contract);
+        for seller in &self.sellers {
+            counter_token_total += counter_token.balance(seller);
+            token_total += token.balance(seller);
+        }
+        assert_eq!((counter_token_total, token_total), self.minted, "Tokens not conserved");
+
+        let state = self.state();
+
+        // A Closed or Aborted auction stays that way until it is reset
+        if matches!(state_before, Some(State::Closed) | Some(State::Aborted)) && !matches!(op, Op::Reset) {
+            assert_eq!(state, state_before, "Left {state_before:?} without a reset");
+        }
+
+        let Some(state) = state else {
+            // Without an auction, the contract holds nothing in escrow
+            assert_eq!((counter_token.balance(&contract), token.balance(&contract)), (0, 0), "Escrow left after reset");
+            self.highest_bid = None;
+            return
+        };
+
+        // The contract holds exactly the escrow it tracks, and nothing once the auction has ended
+        let summary = self.client().get_auction_summary();
+        assert_eq!((summary.balances.counter_token, summary.balances.token),
+                   (counter_token.balance(&contract), token.balance(&contract)), "Escrow not tracked");
+        if matches!(state, State::Closed | State::Aborted) {
+            assert_eq!(summary.balances, Default::default(), "Escrow left after the auction ended");
+        }
+
+        // The bid never decreases, and never exceeds the maximum bid the escrow covers
+        if matches!(state, State::NotStarted | State::Running | State::Paused | State::Cancelling) {
+            let bid = summary.bid_info.current_bid;
+            assert!(bid <= summary.data.bid_max_amount, "Bid above the maximum bid");
+            assert!(summary.data.bid_max_amount <= summary.balances.counter_token, "Maximum bid not covered by the escrow");
+            if let Some((id, highest_bid)) = self.highest_bid {
+                if id == summary.id {
+                    assert!(bid >= highest_bid, "Bid decreased from {highest_bid} to {bid}");
+                }
+            }
+            self.highest_bid = Some((summary.id, bid));
+        }
+    }
+}
@@ -12,6 +12,63 @@ pub struct Data {
     pub bid_incr_interval: u32,
     pub bid_incr_times: u32,
     pub bid_max_amount: i128,
+    pub cancel_notice: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct EscrowTerms {
+    pub arbiter: Address,
+    pub dispute_window: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct PendingSale {
+    pub seller: Address,
+    pub price: i128,
+    pub release_ledger: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct SellOrder {
+    pub auction_id: u32,
+    pub seller: Address,
+    pub min_price: i128,
+    pub valid_until_ledger: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct SaleRecord {
+    pub seller: Address,
+    pub price: i128,
+    pub ledger: u32,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct AuctionRecord {
+    pub id: u32,
+    pub data: Data,
+    pub state: State,
+    pub sale: Sale,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Sale {
+    Unsold,
+    Sold(SaleRecord),
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
+pub struct Balances {
+    pub counter_token: i128,
+    pub token: i128,
 }
 
 #[contracttype]
@@ -24,13 +81,30 @@ pub struct BidInfo {
     pub max_bid_ledger: u32,
     pub next_bid: i128,
     pub next_bid_ledger: u32,
+    pub start_ledger: u32,
+    pub state: State,
 }
 
-
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct AuctionSummary {
+    pub id: u32,
+    pub data: Data,
+    pub state: State,
+    pub bid_info: BidInfo,
+    pub balances: Balances,
+    pub sale_price: Option<i128>,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum State {
+    NotStarted,
     Running,
+    Paused,
+    Cancelling,
+    PendingRelease,
+    Disputed,
     Fulfilled,
     Closed,
     Aborted,
@@ -40,10 +114,25 @@ pub enum State {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum DataKey {
-    State,
-    Data,
+    Admin,
+    AuctionId,
+    AuctionCount,
+    Version,
 }
 
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum AuctionKey {
+    State(u32),
+    Data(u32),
+    Escrow(u32),
+    PendingSale(u32),
+    PausedLedger(u32),
+    CancelLedger(u32),
+    Balances(u32),
+    Sale(u32),
+    Operator(u32),
+}
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Status {
@@ -56,8 +145,41 @@ pub enum Status {
     NotInitialised,
     NotRunning,
     BidMustBePositive, 
-    TransferError,   
     Reset,
+    PendingRelease,
+    NotPendingRelease,
+    DisputeRaised,
+    DisputeWindowOpen,
+    DisputeWindowClosed,
+    NotDisputed,
+    Released,
+    Refunded,
+    Paused,
+    NotPaused,
+    Resumed,
+    Amended,
+    AmendmentLowersPrice,
+    Scheduled,
+    NotStarted,
+    StartLedgerInPast,
+    Cancelling,
+    NoticePeriod,
+    AdminSet,
+    Rescued,
+    ExceedsUntracked,
+    ZeroInterval,
+    Overflow,
+    DurationTooLong,
+    IdenticalTokens,
+    BuyerIsContract,
+    Bumped,
+    Upgraded,
+    Migrated,
+    UpToDate,
+    OperatorSet,
+    NotManager,
+    EscrowHeld,
+    ExceedsEscrow,
 }
 
 #[contracterror]
@@ -68,4 +190,11 @@ pub enum Error {
     AlreadyIntitialised= 2,
     NotRunning = 3,
     NotYetClosed = 4,
+    TransferAmountMismatch = 5,
+    TransferFailed = 6,
+    PriceBelowMinimum = 8,
+    SaleExpired = 9,
+    WrongAuction = 10,
+    NotDeployer = 11,
+    NotMigrated = 12,
 }
\ No newline at end of file
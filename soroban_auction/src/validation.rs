@@ -0,0 +1,70 @@
+use auction_pricing::ScheduleParams;
+use soroban_sdk::{Address, Env};
+
+use crate::types::{EscrowTerms, Status};
+use crate::TTL_BUFFER;
+
+/// Validate the parameters of `setup_auction`, and compute the maximum price with checked arithmetic.
+/// Returns the Status describing the first invalid parameter
+#[allow(clippy::too_many_arguments)]
+pub fn validate_setup(env: &Env,
+                      buyer: &Address,
+                      auction_token: &Address,
+                      counter_token: &Address,
+                      starting_bid: i128,
+                      bid_incr_amount: i128,
+                      bid_incr_times: u32,
+                      bid_incr_interval: u32,
+                      escrow: &Option<EscrowTerms>,
+                      auction_start_ledger: u32,
+                      cancel_notice: u32,
+                     ) -> Result<i128, Status> {
+
+    // The contract can not buy from itself
+    if *buyer == env.current_contract_address() {
+        return Err(Status::BuyerIsContract)
+    }
+    if auction_token == counter_token {
+        return Err(Status::IdenticalTokens)
+    }
+
+    // Check if the starting bid and bid-increase amount are positive
+    if starting_bid < 1 || bid_incr_amount < 1 {
+        return Err(Status::BidMustBePositive)
+    }
+
+    // The bid is calculated per interval, so an interval of 0 ledgers is meaningless
+    if bid_incr_interval == 0 {
+        return Err(Status::ZeroInterval)
+    }
+
+    // The auction can be scheduled to start in the future, but not in the past
+    let current_ledger = env.ledger().sequence();
+    if auction_start_ledger < current_ledger {
+        return Err(Status::StartLedgerInPast)
+    }
+
+    // The maximum price must fit in an i128, and the last ledger of the schedule in a u32
+    let params = ScheduleParams {
+        start_ledger: auction_start_ledger,
+        start_bid: starting_bid,
+        incr_amount: bid_incr_amount,
+        incr_interval: bid_incr_interval,
+        incr_times: bid_incr_times,
+    };
+    let max_price: i128 = params.max_bid().ok_or(Status::Overflow)?;
+    let duration: u32 = params.max_bid_ledger().ok_or(Status::Overflow)? - current_ledger;
+
+    // The contract instance must stay live for the whole auction, which is limited by the network's maximum TTL.
+    // The dispute window and cancellation notice are added to the current ledger later on, and the auction is kept live
+    // until they end as well, so they are held to the same limit
+    let max_ttl: u32 = env.storage().max_ttl();
+    let dispute_window: u32 = escrow.as_ref().map_or(0, |escrow| escrow.dispute_window);
+    if duration.saturating_add(TTL_BUFFER) > max_ttl
+        || dispute_window.saturating_add(TTL_BUFFER) > max_ttl
+        || cancel_notice.saturating_add(TTL_BUFFER) > max_ttl {
+        return Err(Status::DurationTooLong)
+    }
+
+    Ok(max_price)
+}